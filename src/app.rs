@@ -1,39 +1,169 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use crate::deals::{DealScore, PriceHistory, score_lots};
 use crate::gql;
-use crate::gql::{Data, Vars};
-use crate::items::{AllSets, ClassSets, Item, ItemHasOption, ItemOptionType, SetItems};
-use gql_client::Client;
+use crate::items::{
+    AllSets, ClassSets, Item, ItemCategory, ItemHasOption, ItemOptionType, ItemOptions, ItemType,
+    MAX_OPTION_LEVEL, OPTION_SCHEMA, SearchMode, SetItems, default_gear,
+};
+use crate::market::{
+    DEFAULT_CACHE_TTL, DEFAULT_MAX_CONCURRENT_REQUESTS, DEFAULT_MAX_REQUESTS_PER_MINUTE,
+    MarketCache, MarketResult, RequestQueue, Traffic, format_age,
+};
+use crate::notify;
+use crate::planner::{Candidate, PurchasePlan, plan};
+use crate::purchases::{Purchase, PurchaseLog, format_date};
+use crate::search::{MarketQuery, SavedSearch, SavedSearches, SortOrder};
+use crate::session::Session;
+use crate::settings::{
+    DEFAULT_ENDPOINT, DEFAULT_LANGUAGE, DEFAULT_LOT_URL_TEMPLATE, Settings, SettingsDraft,
+    ThemeChoice,
+};
+use crate::shortcuts::{self, CHEAT_SHEET, Shortcut};
+use crate::ui_state::UiState;
+use futures_util::FutureExt;
 use iced::alignment::Horizontal;
+use iced::theme::palette;
 use iced::widget::{Container, container, horizontal_rule, row};
-use iced::{Alignment, Color, Element, Font, Length, Pixels, Task, widget};
+use iced::{
+    Alignment, Border, Color, Element, Event, Font, Length, Pixels, Point, Size, Subscription,
+    Task, Theme, event, keyboard, task, widget, window,
+};
 use ron::ser::{PrettyConfig, to_string_pretty};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
 use std::fmt::{self, Debug, Display, Formatter};
-use std::path::PathBuf;
+use std::mem::discriminant;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 
 const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
-const ENDPOINT: &str = "https://mudream.online/api/graphql";
+/// File extension of exported collections, registered as `application/x-dream-collection`.
+pub const COLLECTION_EXTENSION: &str = "dreamcol";
+/// How often the window layout is saved, besides on close.
+const UI_STATE_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How collection files passed on the command line are opened.
+#[derive(Debug, Clone, Default)]
+pub enum Launch {
+    #[default]
+    Default,
+    /// Show a collection file without touching the player's own collection.
+    View(PathBuf),
+    /// Merge owned options from collection files into the player's collection.
+    Import(Vec<PathBuf>),
+}
+
+pub const USAGE: &str = "usage: dream-collections [FILE | --import FILE...]";
+
+impl Launch {
+    /// Parses `[FILE | --import FILE...]`, a single file is opened read-only.
+    /// Arguments after `--` are always files.
+    pub fn from_args(args: impl Iterator<Item = OsString>) -> Result<Self, String> {
+        let mut import = false;
+        let mut only_paths = false;
+        let mut paths = vec![];
+
+        for arg in args {
+            match arg.to_str() {
+                Some("--") if !only_paths => only_paths = true,
+                Some("--import") if !only_paths => import = true,
+                Some(flag) if !only_paths && flag.starts_with('-') => {
+                    return Err(format!("unknown option: {flag}"));
+                }
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        match (import, paths.len()) {
+            (true, 0) => Err("--import needs at least one file".to_string()),
+            (true, _) => Ok(Launch::Import(paths)),
+            (false, 0) => Ok(Launch::Default),
+            (false, 1) => Ok(Launch::View(paths.swap_remove(0))),
+            (false, _) => {
+                Err("only one file can be viewed, use --import to merge several".to_string())
+            }
+        }
+    }
+}
 
 /// Messages emitted by the application and its widgets.
 #[derive(Debug, Clone)]
 pub enum Message {
     ChangePage(Page),
     ChangeSet(String),
+    ShowClassGrid(bool),
+    SetQuery(String),
+    JumpToSet(Page, String),
+    JumpToFirstSet,
 
     UpdateItem(Arc<Mutex<Item>>, ItemOptionType, ItemHasOption),
+    UpdateWanted(Arc<Mutex<Item>>, ItemOptionType, bool),
+    ChangeSearchMode(Arc<Mutex<Item>>, SearchMode),
 
     SaveCollections,
+    ExportCollections,
+    ConfirmExport,
+    CancelExport,
     SearchMarket(Arc<Mutex<Item>>),
     ClearOffers,
 
-    MarketSearchResult((String, Option<Data>)),
+    MarketSearchResult(Option<ItemOptionType>, MarketResult),
+    RefreshOffers,
+    SortByDeal(bool),
+
+    ChangeScreen(Screen),
+    SettingsEdited(SettingsDraft),
+    ApplySettings,
+    ChangeOffersSplit(f32),
+    WindowResized(Size),
+    WindowMoved(Point),
+    WindowCloseRequested(window::Id),
+    SaveUiState,
+    WindowFocused(bool),
+    NotificationSent(Option<u32>, NotificationTarget),
+    NotificationClicked(u32),
+    Shortcut(Shortcut),
+    QueryNames(String),
+    QueryItemType(ItemType, bool),
+    QueryOption(ItemOptionType, bool),
+    QuerySort(SortOrder),
+    RunQuery,
+    QueryResult(Option<String>, MarketResult),
+
+    PlanPurchases(PlanScope),
+    PlannerResult(PlannedItems, ItemOptionType, MarketResult),
+    ClosePlanner,
+    ToggleOffer(usize),
+    HideOwnLots(bool),
+    ExcludeOwnFromCheapest(bool),
+    OpenLot(String),
+    MarkPurchased(usize),
+    TogglePurchaseOption(ItemOptionType, bool),
+    ConfirmPurchase,
+    CancelPurchase,
+
+    SessionInput(String),
+    Login,
+    Logout,
+
+    SaveSearch(Arc<Mutex<Item>>),
+    RunSavedSearch(usize),
+    EditSavedSearch(usize),
+    DuplicateSavedSearch(usize),
+    DeleteSavedSearch(usize),
+    SavedSearchEdited(SavedSearch),
+    ApplySavedSearchEdit,
+    CancelSavedSearchEdit,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerCollection {
     pub collection: Vec<Arc<Mutex<ClassSets>>>,
+    #[serde(default = "default_gear")]
+    pub gear: Vec<Arc<Mutex<Item>>>,
 }
 
 impl Default for PlayerCollection {
@@ -115,11 +245,71 @@ impl Default for PlayerCollection {
                     SetItems::new(AllSets::PhoenixSoul),
                 ]))),
             ],
+            gear: default_gear(),
         }
     }
 }
 
 impl PlayerCollection {
+    /// Reads a collection file, an empty file is a fresh collection.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|err| format!("Nie można odczytać {}: {err}", path.display()))?;
+
+        if data.trim().is_empty() {
+            Ok(PlayerCollection::default())
+        } else {
+            ron::from_str(&data)
+                .map_err(|err| format!("Niepoprawny plik kolekcji {}: {err}", path.display()))
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        let data = to_string_pretty(self, PrettyConfig::new()).unwrap();
+
+        if let Err(err) = std::fs::write(path, data) {
+            eprintln!("failed to save collections: {err}");
+        }
+    }
+
+    /// Marks every option owned in `other` as owned here too.
+    pub fn merge(&mut self, other: &PlayerCollection) {
+        for other_class in other.collection.iter() {
+            let other_class = other_class.lock().unwrap();
+
+            let Some(class) = self
+                .collection
+                .iter()
+                .find(|c| discriminant(&*c.lock().unwrap()) == discriminant(&*other_class))
+            else {
+                continue;
+            };
+            let class = class.lock().unwrap();
+
+            for other_set in other_class.sets() {
+                if let Some(set) = class.sets().iter().find(|s| s.set == other_set.set) {
+                    for (item, other_item) in set.items.iter().zip(other_set.items.iter()) {
+                        merge_item(item, other_item);
+                    }
+                }
+            }
+        }
+
+        for other_item in other.gear.iter() {
+            let (name, item_type) = {
+                let other_guard = other_item.lock().unwrap();
+                (other_guard.name.clone(), other_guard.item_type)
+            };
+
+            if let Some(item) = self.gear.iter().find(|item| {
+                let item_guard = item.lock().unwrap();
+                item_guard.name == name && item_guard.item_type == item_type
+            }) {
+                merge_item(item, other_item);
+            }
+        }
+    }
+
     pub fn update_class_item(
         &mut self,
         item: Arc<Mutex<Item>>,
@@ -130,26 +320,208 @@ impl PlayerCollection {
 
         item_guard.options.lock().unwrap().0.insert(option, enabled);
     }
+
+    /// Gear of the given category that a class can use.
+    pub fn class_gear(&self, class: &ClassSets, category: ItemCategory) -> Vec<Arc<Mutex<Item>>> {
+        self.gear
+            .iter()
+            .filter(|item| {
+                let item_type = item.lock().unwrap().item_type.unwrap_or_default();
+
+                item_type.category() == category
+                    && (category != ItemCategory::Weapon
+                        || class.weapon_types().contains(&item_type))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Directory holding the collections and every other app file.
+pub fn app_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_default()
+        .join("dream_collections")
+}
+
+/// Parses an environment variable, falling back to `default` when unset or invalid.
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn merge_item(item: &Arc<Mutex<Item>>, other: &Arc<Mutex<Item>>) {
+    let other_options = other.lock().unwrap().options.lock().unwrap().clone();
+    let item_guard = item.lock().unwrap();
+    let mut options = item_guard.options.lock().unwrap();
+
+    for (option, has_option) in other_options.0 {
+        if has_option {
+            options.0.insert(option, true);
+        }
+    }
+}
+
+/// Which part of the collection a purchase plan covers.
+#[derive(Debug, Clone, Copy)]
+pub enum PlanScope {
+    Set,
+    Class,
+}
+
+/// Planned items, by index and type, that one planner query looks for.
+type PlannedItems = Vec<(usize, ItemType)>;
+
+/// Purchase plan being built from per-option market queries.
+struct PlannerState {
+    id: u64,
+    title: String,
+    /// Titles of the planned collection items, by index.
+    items: Vec<String>,
+    missing: Vec<(usize, BTreeSet<ItemOptionType>)>,
+    candidates: BTreeMap<String, Candidate>,
+    pending: usize,
+    plans: Vec<PurchasePlan>,
+    requests: Vec<task::Handle>,
+}
+
+/// Bought lot waiting for the player to tick the options it carries.
+struct PendingPurchase {
+    /// Index into `offers`.
+    index: usize,
+    item: Arc<Mutex<Item>>,
+    options: BTreeSet<ItemOptionType>,
+}
+
+/// Top level screen of the window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Screen {
+    #[default]
+    Collections,
+    MarketQuery,
+    Settings,
+}
+
+/// What a clicked desktop notification brings into view.
+#[derive(Debug, Clone)]
+pub enum NotificationTarget {
+    Item(Arc<Mutex<Item>>),
+    Screen(Screen),
+}
+
+/// Lot found by the cross-set search.
+struct QueryLot {
+    /// Name the lot was queried by, if any.
+    name: Option<String>,
+    lot: gql::Item,
+    /// Collection entries the lot would fill, with the options it adds.
+    fills: Vec<String>,
+}
+
+/// Free-form market search across every set.
+#[derive(Default)]
+struct QueryState {
+    query: MarketQuery,
+    /// Comma separated names as typed.
+    names: String,
+    id: u64,
+    pending: usize,
+    lots: Vec<QueryLot>,
+    requests: Vec<task::Handle>,
 }
 
 pub struct AppModel {
+    screen: Screen,
     page: Page,
+    app_dir: PathBuf,
     config_dir: PathBuf,
+    /// Collection file opened from the command line without importing it.
+    read_only: Option<PathBuf>,
+    /// Export file waiting for the player to confirm overwriting it.
+    pending_export: Option<PathBuf>,
     collections: PlayerCollection,
     current_class: Arc<Mutex<ClassSets>>,
-    current_set: Option<SetItems>,
+    current_items: Vec<Arc<Mutex<Item>>>,
 
     set_options: Vec<SetItems>,
     set_selected: Option<String>,
+    /// Index into `current_items` that keyboard shortcuts act on.
+    focused_item: Option<usize>,
+    /// Text typed in the quick set search.
+    set_query: String,
+    show_cheat_sheet: bool,
+    /// Shows every set of the class as a grid instead of one set at a time.
+    show_class_grid: bool,
 
     offers: (String, Vec<gql::Item>),
+    /// Options each lot in `offers` carries, by index, known from the
+    /// per-option queries that returned it.
+    offer_options: Vec<BTreeSet<ItemOptionType>>,
+    offers_currency: Option<String>,
+    /// Fetch time of the oldest cached part of the offers.
+    offers_cached_at: Option<SystemTime>,
+    /// Whether any part of the offers came fresh from the market.
+    offers_fresh: bool,
+    /// Queries of the offers still running.
+    offers_pending: usize,
+    last_search: Option<SavedSearch>,
+    /// Deal score of each lot in `offers`, by index.
+    offer_scores: Vec<Option<DealScore>>,
+    sort_by_deal: bool,
+    price_history: PriceHistory,
+    price_history_path: PathBuf,
+
+    planner: Option<PlannerState>,
+    /// Request id of the latest planner or cross-set search batch.
+    batch_id: u64,
+    market_query: QueryState,
+    cache: MarketCache,
+    queue: RequestQueue,
+    traffic: Traffic,
+    market_requests: Vec<task::Handle>,
+    expanded_offer: Option<usize>,
+    pending_purchase: Option<PendingPurchase>,
+    hide_own_lots: bool,
+    exclude_own_from_cheapest: bool,
+    /// Marketplace page of a lot, `{id}` is replaced with the lot id.
+    lot_url_template: String,
+
+    searches: SavedSearches,
+    searches_path: PathBuf,
+    purchases: PurchaseLog,
+    purchases_path: PathBuf,
+    editing_search: Option<(usize, SavedSearch)>,
+
+    session: Session,
+    session_path: PathBuf,
+    session_input: String,
+
+    settings: Settings,
+    settings_path: PathBuf,
+    /// Settings being edited on the settings screen.
+    settings_draft: Option<SettingsDraft>,
+    /// Why the draft could not be saved.
+    settings_error: Option<String>,
+    /// Theme resolved from `settings.theme`.
+    theme: Theme,
+
+    ui_state_path: PathBuf,
+    /// Last state written to `ui_state_path`.
+    saved_ui_state: UiState,
+    window_size: Size,
+    window_position: Option<Point>,
+    /// Share of the items and offers columns given to the offers.
+    offers_split: f32,
+    window_focused: bool,
+    /// Targets of notifications still on the desktop, by notification id.
+    notifications: BTreeMap<u32, NotificationTarget>,
 }
 
-impl Default for AppModel {
-    fn default() -> Self {
-        let config_dir = dirs::config_dir().unwrap_or_default();
-
-        let app_dir = config_dir.join("dream_collections");
+impl AppModel {
+    pub fn new(launch: Launch, ui_state: UiState) -> Self {
+        let app_dir = app_dir();
 
         if !app_dir.exists() {
             std::fs::create_dir_all(&app_dir).unwrap();
@@ -161,16 +533,41 @@ impl Default for AppModel {
             std::fs::File::create(&file_path).unwrap();
         }
 
-        let collections: PlayerCollection = {
-            let data = std::fs::read_to_string(&file_path).unwrap_or_default();
-
-            if data.is_empty() {
-                PlayerCollection::default()
-            } else {
-                ron::from_str(&data).unwrap_or_default()
+        let mut errors = vec![];
+        let mut read_only = None;
+        let mut collections = PlayerCollection::load(&file_path).unwrap_or_else(|err| {
+            // Never overwrite a collection that failed to load.
+            errors.push(err);
+            read_only = Some(file_path.clone());
+            PlayerCollection::default()
+        });
+
+        match launch {
+            Launch::Default => {}
+            Launch::View(path) => match PlayerCollection::load(&path) {
+                Ok(viewed) => {
+                    collections = viewed;
+                    read_only = Some(path);
+                }
+                Err(err) => errors.push(err),
+            },
+            Launch::Import(paths) => {
+                let mut imported = false;
+
+                for path in paths {
+                    match PlayerCollection::load(&path) {
+                        Ok(other) => {
+                            collections.merge(&other);
+                            imported = true;
+                        }
+                        Err(err) => errors.push(err),
+                    }
+                }
+                if imported && read_only.is_none() {
+                    collections.save(&file_path);
+                }
             }
-        };
-
+        }
         let current_class = collections
             .collection
             .iter()
@@ -178,39 +575,323 @@ impl Default for AppModel {
             .cloned()
             .unwrap_or_else(|| Arc::new(Mutex::new(ClassSets::DarkWizard(vec![]))));
 
-        let set_options = match &*current_class.lock().unwrap() {
-            ClassSets::DarkWizard(sets) => sets.clone(),
-            ClassSets::DarkKnight(sets) => sets.clone(),
-            ClassSets::Elf(sets) => sets.clone(),
-            ClassSets::Summoner(sets) => sets.clone(),
-            ClassSets::MagicGladiator(sets) => sets.clone(),
-            ClassSets::DarkLord(sets) => sets.clone(),
-            ClassSets::RageFighter(sets) => sets.clone(),
-        };
+        let set_options = current_class.lock().unwrap().sets().clone();
+
+        let searches_path = SavedSearches::path(&app_dir);
+        let searches = SavedSearches::load(&searches_path).unwrap_or_else(|err| {
+            errors.push(err);
+            SavedSearches::default()
+        });
+
+        let settings_path = Settings::path(&app_dir);
+        let settings = Settings::load(&settings_path).unwrap_or_else(|err| {
+            errors.push(err);
+            Settings::default()
+        });
+
+        // Limits and theme are filled in by `apply_settings` below.
+        let cache = MarketCache::new(app_dir.join("cache"), DEFAULT_CACHE_TTL);
+        let queue = RequestQueue::new(
+            DEFAULT_MAX_CONCURRENT_REQUESTS,
+            DEFAULT_MAX_REQUESTS_PER_MINUTE,
+        );
+
+        let price_history_path = PriceHistory::path(&app_dir);
+        let price_history = PriceHistory::load(&price_history_path).unwrap_or_else(|err| {
+            errors.push(err);
+            PriceHistory::default()
+        });
+
+        let purchases_path = PurchaseLog::path(&app_dir);
+        let purchases = PurchaseLog::load(&purchases_path).unwrap_or_else(|err| {
+            errors.push(err);
+            PurchaseLog::default()
+        });
+
+        let session_path = Session::path(&app_dir);
+        let session = Session::load(&session_path);
+
+        for err in errors.iter() {
+            eprintln!("{err}");
+        }
 
         // Construct the app model with the runtime's core.
-        AppModel {
+        let mut model = AppModel {
+            screen: Screen::default(),
             page: Page::DarkWizard,
+            app_dir: app_dir.clone(),
             config_dir: file_path,
+            read_only,
+            pending_export: None,
             collections,
             current_class,
-            current_set: None,
+            current_items: vec![],
 
             set_options,
             set_selected: None,
+            focused_item: None,
+            set_query: String::new(),
+            show_cheat_sheet: false,
+            show_class_grid: false,
 
             offers: (String::new(), vec![]),
+            offer_options: vec![],
+            offers_currency: None,
+            offers_cached_at: None,
+            offers_fresh: false,
+            offers_pending: 0,
+            last_search: None,
+            offer_scores: vec![],
+            sort_by_deal: false,
+            price_history,
+            price_history_path,
+
+            planner: None,
+            batch_id: 0,
+            market_query: QueryState {
+                query: MarketQuery {
+                    sort: settings.default_sort,
+                    ..MarketQuery::default()
+                },
+                ..QueryState::default()
+            },
+            cache,
+            queue,
+            traffic: Traffic::from_env(),
+            market_requests: vec![],
+            expanded_offer: None,
+            pending_purchase: None,
+            hide_own_lots: false,
+            exclude_own_from_cheapest: true,
+            lot_url_template: String::new(),
+
+            searches,
+            searches_path,
+            purchases,
+            purchases_path,
+            editing_search: None,
+
+            session,
+            session_path,
+            session_input: String::new(),
+
+            settings,
+            settings_path,
+            settings_draft: None,
+            settings_error: None,
+            theme: Theme::default(),
+
+            ui_state_path: UiState::path(&app_dir),
+            saved_ui_state: ui_state.clone(),
+            window_size: Size::new(ui_state.width, ui_state.height),
+            window_position: ui_state.position.map(|(x, y)| Point::new(x, y)),
+            offers_split: ui_state.offers_split.clamp(0.2, 0.7),
+            window_focused: true,
+            notifications: BTreeMap::new(),
+        };
+
+        model.apply_settings();
+        model.cache.prune();
+
+        let _ = model.update(Message::ChangePage(ui_state.page));
+
+        if let Some(set) = ui_state.set
+            && model.set_names().contains(&set)
+        {
+            let _ = model.update(Message::ChangeSet(set));
         }
+
+        model.offers.0 = errors.join("\n");
+
+        model
     }
 }
 
 impl AppModel {
     pub fn title(&self) -> String {
-        format!("Dream Collections by Nemessis - {}", REPOSITORY)
+        match &self.read_only {
+            Some(path) => format!(
+                "Dream Collections by Nemessis - {} (tylko do odczytu)",
+                path.display()
+            ),
+            None => format!("Dream Collections by Nemessis - {}", REPOSITORY),
+        }
     }
 
     pub fn view(&self) -> Element<'_, Message> {
-        widget::container(self.view_collections()).into()
+        let screen = match self.screen {
+            Screen::Collections => widget::container(self.view_collections()),
+            Screen::MarketQuery => widget::container(self.view_market_query()),
+            Screen::Settings => widget::container(self.view_settings()),
+        };
+
+        if self.show_cheat_sheet {
+            widget::stack![screen, widget::opaque(view_cheat_sheet())].into()
+        } else {
+            screen.into()
+        }
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.theme.clone()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        Subscription::batch([
+            keyboard::on_key_press(|key, modifiers| {
+                shortcuts::from_key(key, modifiers).map(Message::Shortcut)
+            }),
+            event::listen_with(|event, _, _| match event {
+                Event::Window(window::Event::Resized(size)) => Some(Message::WindowResized(size)),
+                Event::Window(window::Event::Moved(position)) => {
+                    Some(Message::WindowMoved(position))
+                }
+                Event::Window(window::Event::Focused) => Some(Message::WindowFocused(true)),
+                Event::Window(window::Event::Unfocused) => Some(Message::WindowFocused(false)),
+                _ => None,
+            }),
+            window::close_requests().map(Message::WindowCloseRequested),
+            iced::time::every(UI_STATE_SAVE_INTERVAL).map(|_| Message::SaveUiState),
+            Subscription::run(notify::clicks).map(Message::NotificationClicked),
+        ])
+    }
+
+    /// Applies the settings, letting the environment variables override them.
+    fn apply_settings(&mut self) {
+        let settings = &self.settings;
+
+        self.theme = settings.theme.theme();
+        self.cache.ttl = Duration::from_secs(env_or(
+            "DREAM_COLLECTIONS_CACHE_TTL",
+            settings.cache_ttl_secs,
+        ));
+        self.queue.set_limits(
+            env_or(
+                "DREAM_COLLECTIONS_MAX_CONCURRENT_REQUESTS",
+                settings.max_concurrent_requests,
+            ),
+            env_or(
+                "DREAM_COLLECTIONS_MAX_REQUESTS_PER_MINUTE",
+                settings.max_requests_per_minute,
+            ),
+        );
+        self.lot_url_template = std::env::var("DREAM_COLLECTIONS_LOT_URL")
+            .unwrap_or_else(|_| settings.lot_url_template.clone());
+    }
+
+    /// Shows a desktop notification if they are enabled.
+    fn notify(
+        &self,
+        summary: impl Into<String>,
+        body: String,
+        target: NotificationTarget,
+    ) -> Task<Message> {
+        if !self.settings.notifications {
+            return Task::none();
+        }
+
+        Task::future(notify::send(summary.into(), body))
+            .map(move |id| Message::NotificationSent(id, target.clone()))
+    }
+
+    /// Alerts about lots under the alert price, otherwise reports a finished
+    /// search while the window is in the background.
+    fn notify_market(
+        &self,
+        title: &str,
+        lots: &[gql::Item],
+        target: NotificationTarget,
+    ) -> Task<Message> {
+        // Prices in different currencies are compared by their share of
+        // that currency's threshold.
+        let cheapest = lots
+            .iter()
+            .filter(|lot| !lot.is_mine.unwrap_or(false))
+            .flat_map(|lot| lot.prices.iter())
+            .filter_map(|price| {
+                let value = price.value?;
+                let code = price.currency.code.as_ref()?;
+                let threshold = *self.settings.alert_prices.get(code)?;
+                let currency = price.currency.title.clone().unwrap_or(code.clone());
+
+                (value <= threshold).then_some((value, currency, value as f64 / threshold as f64))
+            })
+            .min_by(|(_, _, a), (_, _, b)| a.total_cmp(b));
+
+        if let Some((value, currency, _)) = cheapest {
+            self.notify(
+                format!("Tania oferta: {title}"),
+                format!("Najtańsza oferta: {value} {currency}"),
+                target,
+            )
+        } else if !self.window_focused {
+            self.notify(
+                "Wyszukiwanie zakończone",
+                format!("Znaleziono {} ofert dla {title}", lots.len()),
+                target,
+            )
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Opens the class and set holding `item` and focuses it.
+    fn focus_item(&mut self, item: &Arc<Mutex<Item>>) {
+        let location = self.collections.collection.iter().find_map(|class| {
+            let class = class.lock().unwrap();
+
+            class.sets().iter().find_map(|set| {
+                set.items
+                    .iter()
+                    .any(|other| Arc::ptr_eq(other, item))
+                    .then(|| (Page::of(&class), set.set_string.clone()))
+            })
+        });
+
+        match location {
+            Some((page, set)) => {
+                let _ = self.update(Message::JumpToSet(page, set));
+            }
+            None => {
+                // Gear is listed per category under the class on screen.
+                let category = item
+                    .lock()
+                    .unwrap()
+                    .item_type
+                    .unwrap_or_default()
+                    .category();
+                let _ = self.update(Message::ChangeSet(category.to_string()));
+            }
+        }
+
+        self.focused_item = self
+            .current_items
+            .iter()
+            .position(|current| Arc::ptr_eq(current, item));
+    }
+
+    /// Window layout and selection to restore on the next launch.
+    fn ui_state(&self) -> UiState {
+        UiState {
+            width: self.window_size.width,
+            height: self.window_size.height,
+            position: self
+                .window_position
+                .map(|position| (position.x, position.y)),
+            page: self.page,
+            set: self.set_selected.clone(),
+            offers_split: self.offers_split,
+        }
+    }
+
+    /// Saves the window layout and selection when they changed since the last save.
+    fn save_ui_state(&mut self) {
+        let ui_state = self.ui_state();
+
+        if ui_state != self.saved_ui_state {
+            ui_state.save(&self.ui_state_path);
+            self.saved_ui_state = ui_state;
+        }
     }
 
     /// Handles messages emitted by the application and its widgets.
@@ -222,244 +903,2412 @@ impl AppModel {
             Message::ChangePage(page) => {
                 self.set_options.clear();
 
-                self.page = page;
-                self.current_class = self
+                // A viewed file may lack the class, fall back to the first one it has.
+                let class = self
                     .collections
                     .collection
                     .iter()
-                    .find(|c| {
-                        matches!(
-                            (self.page, &*c.lock().unwrap()),
-                            (Page::DarkWizard, ClassSets::DarkWizard(_))
-                                | (Page::DarkKnight, ClassSets::DarkKnight(_))
-                                | (Page::Elf, ClassSets::Elf(_))
-                                | (Page::Summoner, ClassSets::Summoner(_))
-                                | (Page::MagicGladiator, ClassSets::MagicGladiator(_))
-                                | (Page::DarkLord, ClassSets::DarkLord(_))
-                                | (Page::RageFighter, ClassSets::RageFighter(_))
-                        )
-                    })
-                    .cloned()
-                    .unwrap();
-
-                self.set_options = match &*self.current_class.lock().unwrap() {
-                    ClassSets::DarkWizard(sets) => sets.clone(),
-                    ClassSets::DarkKnight(sets) => sets.clone(),
-                    ClassSets::Elf(sets) => sets.clone(),
-                    ClassSets::Summoner(sets) => sets.clone(),
-                    ClassSets::MagicGladiator(sets) => sets.clone(),
-                    ClassSets::DarkLord(sets) => sets.clone(),
-                    ClassSets::RageFighter(sets) => sets.clone(),
-                };
+                    .find(|c| Page::of(&c.lock().unwrap()) == page)
+                    .or_else(|| self.collections.collection.first())
+                    .cloned();
+
+                match class {
+                    Some(class) => {
+                        self.page = Page::of(&class.lock().unwrap());
+                        self.current_class = class;
+                    }
+                    None => {
+                        self.page = page;
+                        self.current_class = Arc::new(Mutex::new(ClassSets::DarkWizard(vec![])));
+                    }
+                }
+
+                self.set_options = self.current_class.lock().unwrap().sets().clone();
+                self.set_selected = None;
+                self.current_items.clear();
+                self.focused_item = None;
             }
             Message::ChangeSet(set) => {
+                self.current_items = match self.set_options.iter().find(|s| s.set_string == set) {
+                    Some(set_items) => set_items.items.to_vec(),
+                    None => ItemCategory::GEAR
+                        .iter()
+                        .find(|category| category.to_string() == set)
+                        .map(|category| {
+                            self.collections
+                                .class_gear(&self.current_class.lock().unwrap(), *category)
+                        })
+                        .unwrap_or_default(),
+                };
                 self.set_selected = Some(set);
-                self.current_set = self
-                    .set_options
-                    .iter()
-                    .find(|s| s.set_string == self.set_selected.clone().unwrap())
-                    .cloned();
+                self.focused_item = (!self.current_items.is_empty()).then_some(0);
+            }
+            Message::ShowClassGrid(show) => {
+                self.show_class_grid = show;
+            }
+            Message::SetQuery(query) => {
+                self.set_query = query;
+            }
+            Message::JumpToSet(page, set) => {
+                self.set_query.clear();
+
+                let task = self.update(Message::ChangePage(page));
+                return task.chain(self.update(Message::ChangeSet(set)));
+            }
+            Message::JumpToFirstSet => {
+                if let Some((set, pages)) = self.set_matches().into_iter().next() {
+                    // Stay on the current class when it has the set too.
+                    let page = if pages.contains(&self.page) {
+                        self.page
+                    } else {
+                        pages[0]
+                    };
+
+                    return self.update(Message::JumpToSet(page, set));
+                }
             }
             Message::UpdateItem(item, option, enabled) => {
                 self.collections.update_class_item(item, option, enabled);
             }
+            Message::UpdateWanted(item, option, wanted) => {
+                item.lock().unwrap().wanted.0.insert(option, wanted);
+            }
+            Message::ChangeSearchMode(item, mode) => {
+                let mut item_guard = item.lock().unwrap();
+
+                // Start custom picks from what the missing mode was searching for.
+                if mode == SearchMode::Custom && item_guard.search_mode == SearchMode::Missing {
+                    item_guard.wanted = item_guard.search_options().unwrap_or_else(|| {
+                        item_guard
+                            .item_type
+                            .map(ItemOptions::for_type)
+                            .unwrap_or_default()
+                    });
+                }
+                item_guard.search_mode = mode;
+            }
             Message::SaveCollections => {
-                let data = to_string_pretty(&self.collections, PrettyConfig::new()).unwrap();
-
-                if let Err(err) = std::fs::write(&self.config_dir, data) {
-                    eprintln!("failed to save collections: {err}");
+                if self.read_only.is_none() {
+                    self.collections.save(&self.config_dir);
+                }
+            }
+            Message::ExportCollections => {
+                let export_dir = dirs::document_dir().unwrap_or_else(|| self.app_dir.clone());
+                let path = export_dir.join(format!("dream-collections.{COLLECTION_EXTENSION}"));
+
+                if path.exists() {
+                    self.pending_export = Some(path);
+                } else {
+                    self.export_collections(&path);
+                }
+            }
+            Message::ConfirmExport => {
+                if let Some(path) = self.pending_export.take() {
+                    self.export_collections(&path);
                 }
             }
+            Message::CancelExport => {
+                self.pending_export = None;
+            }
             Message::ClearOffers => {
+                self.market_requests.clear();
                 self.offers.0 = String::new();
                 self.offers.1.clear();
+                self.offer_options.clear();
+                self.offers_pending = 0;
+                self.expanded_offer = None;
+                self.pending_purchase = None;
+                self.offers_cached_at = None;
+                self.last_search = None;
             }
-            Message::SearchMarket(item) => {
-                self.offers.0 = String::new();
-                self.offers.1.clear();
+            Message::ToggleOffer(index) => {
+                self.expanded_offer = if self.expanded_offer == Some(index) {
+                    None
+                } else {
+                    Some(index)
+                };
+            }
+            Message::HideOwnLots(hide) => {
+                self.hide_own_lots = hide;
+            }
+            Message::ExcludeOwnFromCheapest(exclude) => {
+                self.exclude_own_from_cheapest = exclude;
+            }
+            Message::OpenLot(id) => {
+                let url = self.lot_url_template.replace("{id}", &id);
 
+                if let Err(err) = open::that(&url) {
+                    eprintln!("failed to open {url}: {err}");
+                }
+            }
+            Message::SearchMarket(item) => {
+                self.focused_item = self
+                    .current_items
+                    .iter()
+                    .position(|current| Arc::ptr_eq(current, &item));
                 let item_guard = item.lock().unwrap();
-                let query = item_guard.generate_market_query();
-                let vars = item_guard.generate_gql_vars();
-
-                let item_title = format!(
-                    "{} {}",
-                    item_guard.name.clone().unwrap_or_default(),
-                    item_guard.item_type.clone().unwrap_or_default()
-                );
-
-                return iced::Task::future(async move {
-                    let client = Client::new(ENDPOINT);
 
-                    let result = client
-                        .query_with_vars::<Data, Vars>(&query, vars)
-                        .await
-                        .unwrap();
+                let Some(search) = item_guard.to_search(self.settings.default_sort) else {
+                    self.offers.0 = format!("Masz już wszystkie opcje: {}", item_guard.title());
+                    return Task::none();
+                };
+                drop(item_guard);
 
-                    Message::MarketSearchResult((item_title, result))
-                });
+                return self.search_market(search, true);
             }
 
-            Message::MarketSearchResult((item, data)) => {
-                if let Some(data) = data {
-                    for lot in data.lots.lots {
-                        self.offers.1.push(lot);
+            Message::MarketSearchResult(option, result) => {
+                if !self.queue.is_current(result.request_id) {
+                    return Task::none();
+                }
+
+                self.remember(&result);
+
+                let MarketResult {
+                    request_id: _,
+                    title: item,
+                    key: _,
+                    data,
+                    cached_at,
+                } = result;
+
+                match cached_at {
+                    Some(cached_at) => {
+                        self.offers_cached_at = Some(
+                            self.offers_cached_at
+                                .map_or(cached_at, |oldest| oldest.min(cached_at)),
+                        );
                     }
+                    None => self.offers_fresh = true,
                 }
-                self.offers.0 = format!("Znaleziono {} ofert dla {}", self.offers.1.len(), item);
-            }
-        }
 
-        Task::none()
-    }
+                for lot in data.into_iter().flat_map(|data| data.lots.lots) {
+                    let in_currency = self.offers_currency.as_ref().is_none_or(|code| {
+                        lot.prices
+                            .iter()
+                            .any(|price| price.currency.code.as_ref() == Some(code))
+                    });
 
-    pub fn view_collections(&self) -> Container<'_, Message> {
-        let buttons = container(row(vec![
-            widget::pick_list(&Page::ALL[..], Some(self.page), Message::ChangePage)
-                .placeholder("Wybierz klasę")
-                .into(),
-            widget::pick_list(
-                self.set_options
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>(),
-                self.set_selected.clone(),
-                Message::ChangeSet,
-            )
-            .placeholder("Wybierz set")
-            .into(),
-            widget::button("Wyczyść oferty")
-                .on_press(Message::ClearOffers)
-                .into(),
-            widget::button("Zapisz kolekcje")
-                .on_press(Message::SaveCollections)
-                .into(),
-        ]))
-        .padding(10)
-        .center_x(Length::Fill);
+                    if !in_currency {
+                        continue;
+                    }
 
-        let mut content = widget::column!()
-            .push(buttons)
-            .push(horizontal_rule(Pixels::from(2)));
+                    // A lot returned for several options is listed once.
+                    let known = self
+                        .offers
+                        .1
+                        .iter()
+                        .position(|offer| lot.id.is_some() && offer.id == lot.id);
+                    let index = known.unwrap_or_else(|| {
+                        self.offers.1.push(lot);
+                        self.offer_options.push(BTreeSet::new());
+                        self.offers.1.len() - 1
+                    });
 
-        let mut item_parts = widget::column!().spacing(15);
+                    self.offer_options[index].extend(option.clone());
+                }
 
-        if let Some(set) = self.current_set.as_ref() {
-            for item in set.items.iter() {
-                let item_guard = item.lock().unwrap();
+                self.offers_pending = self.offers_pending.saturating_sub(1);
+
+                if self.offers_pending > 0 {
+                    self.offers.0 = format!(
+                        "Znaleziono {} ofert dla {}, pozostało zapytań: {}",
+                        self.offers.1.len(),
+                        item,
+                        self.offers_pending
+                    );
+                    return Task::none();
+                }
 
-                let mut row = widget::row!().spacing(10).width(Length::Fill);
+                self.market_requests.clear();
+                self.offers.0 = format!("Znaleziono {} ofert dla {}", self.offers.1.len(), item);
 
-                let item_name = format!(
-                    "{} {}",
-                    item_guard.name.clone().unwrap_or_default(),
-                    item_guard.item_type.clone().unwrap_or_default()
+                let history_key = self
+                    .last_search
+                    .as_ref()
+                    .map(SavedSearch::history_key)
+                    .unwrap_or_default();
+
+                // Score against earlier results before these join the history.
+                // A lot is known to carry the options it was returned for.
+                let wanted = self
+                    .last_search
+                    .as_ref()
+                    .map_or(0, |search| search.wanted_options().count());
+                self.offer_scores = score_lots(
+                    &self.offers.1,
+                    &self.price_history.medians(&history_key),
+                    wanted,
+                    |index| self.offer_options.get(index).map_or(0, BTreeSet::len),
                 );
 
-                row = row.push(widget::container(
-                    widget::button(widget::text!("{}", item_name).align_x(Alignment::Center))
-                        .on_press(Message::SearchMarket(item.clone()))
-                        .height(Length::Fixed(200.0))
-                        .width(Length::Fixed(150.0)),
-                ));
+                if self.offers_fresh && !self.offers.1.is_empty() {
+                    self.price_history.record(&history_key, &self.offers.1);
+                    self.price_history.save(&self.price_history_path);
+                }
 
-                let options = item_guard.options.lock().unwrap();
-                let mut col = widget::column!();
+                if self.offers_fresh {
+                    let target = self
+                        .last_search
+                        .as_ref()
+                        .and_then(|search| {
+                            self.find_collection_item(search.set.as_deref(), search.item_type)
+                        })
+                        .map_or(
+                            NotificationTarget::Screen(Screen::Collections),
+                            NotificationTarget::Item,
+                        );
+
+                    return self.notify_market(&item, &self.offers.1, target);
+                }
+            }
+            Message::ChangeScreen(screen) => {
+                self.settings_draft =
+                    (screen == Screen::Settings).then(|| SettingsDraft::from(&self.settings));
+                self.settings_error = None;
+                self.screen = screen;
+            }
+            Message::WindowFocused(focused) => {
+                self.window_focused = focused;
+            }
+            Message::NotificationSent(id, target) => {
+                if let Some(id) = id {
+                    self.notifications.insert(id, target);
+                }
+            }
+            Message::NotificationClicked(id) => {
+                let Some(target) = self.notifications.remove(&id) else {
+                    return Task::none();
+                };
 
-                for (option, has_option) in options.0.clone() {
-                    col = col.push(
-                        widget::container(
-                            widget::checkbox(option.to_string(), has_option)
-                                .on_toggle(move |enabled| {
-                                    let item_clone = item.clone();
-                                    Message::UpdateItem(item_clone, option.clone(), enabled)
-                                })
-                                .spacing(10),
-                        )
-                        .height(Length::Fixed(30.0)),
-                    );
+                self.screen = Screen::Collections;
+                self.settings_draft = None;
+
+                match target {
+                    NotificationTarget::Item(item) => self.focus_item(&item),
+                    NotificationTarget::Screen(screen) => self.screen = screen,
                 }
-                row = row.push(widget::container(col).center_y(Length::Fixed(200.0)));
-                item_parts = item_parts.push(row);
+
+                return window::get_latest().and_then(window::gain_focus);
             }
-        }
+            Message::ChangeOffersSplit(split) => {
+                self.offers_split = split;
+            }
+            Message::WindowResized(size) => {
+                // Minimizing on Windows reports an empty window, keep the
+                // size to restore instead.
+                if size.width > 0.0 && size.height > 0.0 {
+                    self.window_size = size;
+                }
+            }
+            Message::WindowMoved(position) => {
+                self.window_position = Some(position);
+            }
+            Message::SaveUiState => {
+                self.save_ui_state();
+            }
+            Message::WindowCloseRequested(id) => {
+                self.save_ui_state();
 
-        let offers_container = self.view_offers();
+                return window::close(id);
+            }
+            Message::SettingsEdited(draft) => {
+                self.settings_draft = Some(draft);
+            }
+            Message::ApplySettings => {
+                let Some(draft) = self.settings_draft.as_ref() else {
+                    return Task::none();
+                };
 
-        let row = widget::row!()
-            .spacing(20)
-            .push(
-                widget::scrollable(item_parts)
-                    .width(Length::FillPortion(3))
-                    .spacing(16),
-            )
-            .push(
-                widget::scrollable(offers_container)
-                    .width(Length::FillPortion(2))
-                    .spacing(16),
-            );
+                match draft.validate() {
+                    Ok(settings) => {
+                        self.settings = settings;
+                        self.settings.save(&self.settings_path);
+                        self.apply_settings();
+                        self.settings_draft = None;
+                        self.settings_error = None;
+                        self.screen = Screen::Collections;
+                    }
+                    Err(err) => self.settings_error = Some(err),
+                }
+            }
+            Message::Shortcut(shortcut) => {
+                return self.shortcut(shortcut);
+            }
+            Message::QueryNames(names) => {
+                self.market_query.query.names = names
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                self.market_query.names = names;
+            }
+            Message::QueryItemType(item_type, enabled) => {
+                let item_types = &mut self.market_query.query.item_types;
 
-        content = content.push(row);
+                item_types.retain(|t| *t != item_type);
+                if enabled {
+                    item_types.push(item_type);
+                }
+            }
+            Message::QueryOption(option, enabled) => {
+                if enabled {
+                    self.market_query.query.options.insert(option);
+                } else {
+                    self.market_query.query.options.remove(&option);
+                }
+            }
+            Message::QuerySort(sort) => {
+                self.market_query.query.sort = sort;
+            }
+            Message::RunQuery => {
+                return self.run_market_query();
+            }
+            Message::QueryResult(name, result) => {
+                self.remember(&result);
 
-        widget::container(content)
-            .padding(30)
-            .align_x(Horizontal::Center)
-    }
+                if result.request_id != self.market_query.id {
+                    return Task::none();
+                }
 
-    pub fn view_offers(&self) -> Container<'_, Message> {
-        let mut col = widget::column!();
+                for lot in result.data.into_iter().flat_map(|data| data.lots.lots) {
+                    if self
+                        .market_query
+                        .lots
+                        .iter()
+                        .any(|known| known.lot.id.is_some() && known.lot.id == lot.id)
+                    {
+                        continue;
+                    }
 
-        col = col.push(widget::text(self.offers.0.clone()).size(24));
+                    let fills = self.collection_fills(name.as_deref(), &lot);
 
-        for item in self.offers.1.iter() {
-            col = col.push(widget::container({
-                let mut colu = widget::column!().spacing(8);
+                    self.market_query.lots.push(QueryLot {
+                        name: name.clone(),
+                        lot,
+                        fills,
+                    });
+                }
 
-                colu = colu.push(
-                    widget::row!()
+                self.market_query.pending = self.market_query.pending.saturating_sub(1);
+                if self.market_query.pending == 0 {
+                    self.market_query.requests.clear();
+
+                    let lots: Vec<gql::Item> = self
+                        .market_query
+                        .lots
+                        .iter()
+                        .map(|found| found.lot.clone())
+                        .collect();
+
+                    return self.notify_market(
+                        "Wyszukiwarka",
+                        &lots,
+                        NotificationTarget::Screen(Screen::MarketQuery),
+                    );
+                }
+            }
+            Message::PlanPurchases(scope) => {
+                let (title, items) = match scope {
+                    PlanScope::Set => (
+                        self.set_selected.clone().unwrap_or_default(),
+                        self.current_items.clone(),
+                    ),
+                    PlanScope::Class => (
+                        self.page.to_string(),
+                        self.current_class
+                            .lock()
+                            .unwrap()
+                            .sets()
+                            .iter()
+                            .flat_map(|set| set.items.iter().cloned())
+                            .collect(),
+                    ),
+                };
+
+                return self.plan_purchases(title, items);
+            }
+            Message::PlannerResult(items, option, result) => {
+                self.remember(&result);
+
+                let Some(planner) = self.planner.as_mut() else {
+                    return Task::none();
+                };
+                if planner.id != result.request_id {
+                    return Task::none();
+                }
+
+                for lot in result.data.iter().flat_map(|data| data.lots.lots.iter()) {
+                    let Some(lot_id) = lot.id.clone() else {
+                        continue;
+                    };
+                    // Items queried together share a name, the lot type tells them apart.
+                    let Some(item) = items
+                        .iter()
+                        .find(|(_, item_type)| {
+                            items.len() == 1 || lot.type_.as_deref() == item_type.gql_type()
+                        })
+                        .map(|(item, _)| *item)
+                    else {
+                        continue;
+                    };
+
+                    planner
+                        .candidates
+                        .entry(lot_id.clone())
+                        .or_insert_with(|| Candidate {
+                            lot_id,
+                            item,
+                            options: BTreeSet::new(),
+                            prices: lot
+                                .prices
+                                .iter()
+                                .filter_map(|price| {
+                                    Some((price.currency.title.clone()?, price.value?))
+                                })
+                                .collect(),
+                        })
+                        .options
+                        .insert(option.clone());
+                }
+
+                planner.pending = planner.pending.saturating_sub(1);
+
+                if planner.pending == 0 {
+                    let candidates: Vec<Candidate> = planner.candidates.values().cloned().collect();
+                    planner.plans = plan(&planner.missing, &candidates);
+                    planner.requests.clear();
+
+                    if !self.window_focused {
+                        let body = format!("Plan zakupu gotowy: {}", planner.title);
+
+                        return self.notify(
+                            "Plan zakupu",
+                            body,
+                            NotificationTarget::Screen(Screen::Collections),
+                        );
+                    }
+                }
+            }
+            Message::ClosePlanner => {
+                self.planner = None;
+            }
+            Message::SortByDeal(sort) => {
+                self.sort_by_deal = sort;
+            }
+            Message::SessionInput(input) => {
+                self.session_input = input;
+            }
+            Message::Login => {
+                let token = std::mem::take(&mut self.session_input).trim().to_string();
+
+                if !token.is_empty() {
+                    self.session.token = Some(token);
+                    self.session.save(&self.session_path);
+                }
+            }
+            Message::Logout => {
+                self.session.token = None;
+                self.session.save(&self.session_path);
+            }
+            Message::SaveSearch(item) => {
+                let item_guard = item.lock().unwrap();
+
+                let Some(search) = item_guard.to_search(self.settings.default_sort) else {
+                    self.offers.0 = format!("Masz już wszystkie opcje: {}", item_guard.title());
+                    return Task::none();
+                };
+                drop(item_guard);
+
+                self.searches.searches.push(search);
+                self.searches.save(&self.searches_path);
+            }
+            Message::MarkPurchased(index) => {
+                if self.read_only.is_some() {
+                    return Task::none();
+                }
+                let Some(search) = self.last_search.as_ref() else {
+                    return Task::none();
+                };
+                let Some(item) = self.find_collection_item(search.set.as_deref(), search.item_type)
+                else {
+                    self.offers.0 = format!("Brak przedmiotu w kolekcji: {}", search.name);
+                    return Task::none();
+                };
+
+                // Start from the options the lot was found for that the
+                // player is still missing, the player confirms the rest.
+                let options = {
+                    let item_guard = item.lock().unwrap();
+                    let owned = item_guard.options.lock().unwrap();
+
+                    self.offer_options
+                        .get(index)
+                        .into_iter()
+                        .flatten()
+                        .filter(|option| owned.0.get(*option) != Some(&true))
+                        .cloned()
+                        .collect()
+                };
+
+                self.pending_purchase = Some(PendingPurchase {
+                    index,
+                    item,
+                    options,
+                });
+            }
+            Message::TogglePurchaseOption(option, carried) => {
+                if let Some(pending) = self.pending_purchase.as_mut() {
+                    if carried {
+                        pending.options.insert(option);
+                    } else {
+                        pending.options.remove(&option);
+                    }
+                }
+            }
+            Message::ConfirmPurchase => {
+                // A viewed or unreadable collection file is never overwritten.
+                if self.read_only.is_some() {
+                    return Task::none();
+                }
+                let Some(pending) = self.pending_purchase.take() else {
+                    return Task::none();
+                };
+                let Some(lot) = self.offers.1.get(pending.index) else {
+                    return Task::none();
+                };
+                if pending.options.is_empty() {
+                    return Task::none();
+                }
+
+                for option in pending.options.iter() {
+                    self.collections
+                        .update_class_item(pending.item.clone(), option.clone(), true);
+                }
+                self.collections.save(&self.config_dir);
+
+                self.purchases.purchases.push(Purchase {
+                    lot_id: lot.id.clone().unwrap_or_default(),
+                    item: pending.item.lock().unwrap().title(),
+                    options: pending.options.into_iter().collect(),
+                    prices: lot
+                        .prices
+                        .iter()
+                        .filter_map(|price| Some((price.currency.title.clone()?, price.value?)))
+                        .collect(),
+                    purchased_at: SystemTime::now(),
+                });
+                self.purchases.save(&self.purchases_path);
+            }
+            Message::CancelPurchase => {
+                self.pending_purchase = None;
+            }
+            Message::RunSavedSearch(index) => {
+                if let Some(search) = self.searches.searches.get(index).cloned() {
+                    return self.search_market(search, true);
+                }
+            }
+            Message::RefreshOffers => {
+                if let Some(search) = self.last_search.clone() {
+                    return self.search_market(search, false);
+                }
+            }
+            Message::EditSavedSearch(index) => {
+                self.editing_search = self
+                    .searches
+                    .searches
+                    .get(index)
+                    .cloned()
+                    .map(|search| (index, search));
+            }
+            Message::DuplicateSavedSearch(index) => {
+                if let Some(mut search) = self.searches.searches.get(index).cloned() {
+                    search.name = format!("{} (kopia)", search.name);
+
+                    self.searches.searches.insert(index + 1, search);
+                    self.searches.save(&self.searches_path);
+
+                    // Keep an open edit pointing at the search it started from.
+                    if let Some((editing_index, _)) = self.editing_search.as_mut()
+                        && *editing_index > index
+                    {
+                        *editing_index += 1;
+                    }
+                }
+            }
+            Message::DeleteSavedSearch(index) => {
+                if index < self.searches.searches.len() {
+                    self.searches.searches.remove(index);
+                    self.searches.save(&self.searches_path);
+                    self.editing_search = None;
+                }
+            }
+            Message::SavedSearchEdited(search) => {
+                if let Some((_, editing)) = self.editing_search.as_mut() {
+                    *editing = search;
+                }
+            }
+            Message::ApplySavedSearchEdit => {
+                if let Some((index, search)) = self.editing_search.take()
+                    && let Some(saved) = self.searches.searches.get_mut(index)
+                {
+                    *saved = search;
+                    self.searches.save(&self.searches_path);
+                }
+            }
+            Message::CancelSavedSearchEdit => {
+                self.editing_search = None;
+            }
+        }
+
+        Task::none()
+    }
+
+    fn shortcut(&mut self, shortcut: Shortcut) -> Task<Message> {
+        match shortcut {
+            Shortcut::ToggleCheatSheet => {
+                self.show_cheat_sheet = !self.show_cheat_sheet;
+                return Task::none();
+            }
+            Shortcut::Escape if self.show_cheat_sheet => {
+                self.show_cheat_sheet = false;
+                return Task::none();
+            }
+            _ if self.screen != Screen::Collections => return Task::none(),
+            _ => {}
+        }
+
+        let focused = self
+            .focused_item
+            .and_then(|index| self.current_items.get(index))
+            .cloned();
+
+        match shortcut {
+            Shortcut::SelectClass(index) => {
+                if let Some(page) = Page::ALL.get(index) {
+                    return self.update(Message::ChangePage(*page));
+                }
+            }
+            Shortcut::NextSet | Shortcut::PreviousSet => {
+                let names = self.set_names();
+                let current = self
+                    .set_selected
+                    .as_ref()
+                    .and_then(|selected| names.iter().position(|name| name == selected));
+
+                let next = match (shortcut, current) {
+                    (Shortcut::NextSet, Some(index)) => (index + 1) % names.len(),
+                    (Shortcut::PreviousSet, Some(index)) => (index + names.len() - 1) % names.len(),
+                    (Shortcut::PreviousSet, None) => names.len().saturating_sub(1),
+                    _ => 0,
+                };
+
+                if let Some(name) = names.get(next) {
+                    return self.update(Message::ChangeSet(name.clone()));
+                }
+            }
+            Shortcut::NextItem | Shortcut::PreviousItem => {
+                let count = self.current_items.len();
+
+                if count > 0 {
+                    self.focused_item = Some(match (shortcut, self.focused_item) {
+                        (Shortcut::NextItem, Some(index)) => (index + 1).min(count - 1),
+                        (Shortcut::PreviousItem, Some(index)) => index.saturating_sub(1),
+                        _ => 0,
+                    });
+                }
+            }
+            Shortcut::ToggleOption(index) => {
+                if self.read_only.is_some() {
+                    return Task::none();
+                }
+
+                if let Some(item) = focused {
+                    let option = {
+                        let item_guard = item.lock().unwrap();
+                        let options = item_guard.options.lock().unwrap();
+                        options
+                            .0
+                            .iter()
+                            .nth(index)
+                            .map(|(option, has_option)| (option.clone(), !has_option))
+                    };
+
+                    if let Some((option, enabled)) = option {
+                        return self.update(Message::UpdateItem(item, option, enabled));
+                    }
+                }
+            }
+            Shortcut::Save => return self.update(Message::SaveCollections),
+            Shortcut::SearchFocused => {
+                if let Some(item) = focused {
+                    return self.update(Message::SearchMarket(item));
+                }
+            }
+            Shortcut::Escape => return self.update(Message::ClearOffers),
+            Shortcut::ToggleCheatSheet => {}
+        }
+
+        Task::none()
+    }
+
+    /// Sets whose name contains the quick search text, with every class that
+    /// has them.
+    fn set_matches(&self) -> BTreeMap<String, Vec<Page>> {
+        let query = self.set_query.trim().to_lowercase();
+        let mut matches: BTreeMap<String, Vec<Page>> = BTreeMap::new();
+
+        if query.is_empty() {
+            return matches;
+        }
+
+        for class in self.collections.collection.iter() {
+            let class = class.lock().unwrap();
+
+            for set in class.sets() {
+                if set.set_string.to_lowercase().contains(&query) {
+                    matches
+                        .entry(set.set_string.clone())
+                        .or_default()
+                        .push(Page::of(&class));
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Sets of the current class followed by the gear categories, as listed
+    /// in the set pick list.
+    fn set_names(&self) -> Vec<String> {
+        self.set_options
+            .iter()
+            .map(|s| s.to_string())
+            .chain(ItemCategory::GEAR.iter().map(|c| c.to_string()))
+            .collect()
+    }
+
+    fn export_collections(&mut self, path: &Path) {
+        self.collections.save(path);
+        self.offers.0 = format!("Wyeksportowano kolekcje do {}", path.display());
+    }
+
+    /// Shows the results of `search` in the offers panel, superseding any
+    /// search still in flight.
+    fn search_market(&mut self, search: SavedSearch, use_cache: bool) -> Task<Message> {
+        self.clear_offers(&search);
+
+        let request_id = self.queue.next_id();
+        let mut tasks = vec![];
+
+        for (option, vars) in search.generate_gql_vars(self.settings.page_size) {
+            let (task, handle) = self
+                .market_task(search.name.clone(), vars, request_id, use_cache)
+                .map(move |result| Message::MarketSearchResult(option.clone(), result))
+                .abortable();
+
+            tasks.push(task);
+            self.market_requests.push(handle.abort_on_drop());
+        }
+
+        self.offers_pending = tasks.len();
+
+        Task::batch(tasks)
+    }
+
+    /// Results for `vars`, from the cache when allowed and fresh enough,
+    /// otherwise through the request queue.
+    fn market_task(
+        &mut self,
+        title: String,
+        vars: gql::Vars,
+        request_id: u64,
+        use_cache: bool,
+    ) -> Task<MarketResult> {
+        let key = MarketCache::key(
+            &self.settings.endpoint,
+            &self.settings.language,
+            self.session.token.as_deref(),
+            &vars,
+        );
+
+        // Recording and replaying want every query to reach the traffic layer.
+        if use_cache
+            && self.traffic.is_live()
+            && let Some(cached) = self.cache.get(&key)
+        {
+            return Task::done(MarketResult {
+                request_id,
+                title,
+                key,
+                data: Some(cached.data),
+                cached_at: Some(cached.fetched_at),
+            });
+        }
+
+        let client = self
+            .session
+            .client(&self.settings.endpoint, &self.settings.language);
+        let query = self.traffic.clone().query(client, gql::GET_ALL_LOTS, vars);
+
+        // Replayed responses never reach the API, so they skip the limits.
+        let request = if self.traffic.is_replay() {
+            query.boxed()
+        } else {
+            self.queue.run(query).boxed()
+        };
+
+        iced::Task::future(async move {
+            MarketResult {
+                request_id,
+                title,
+                key,
+                data: request.await,
+                cached_at: None,
+            }
+        })
+    }
+
+    /// Stores a fresh live response in the cache.
+    fn remember(&mut self, result: &MarketResult) {
+        if result.cached_at.is_none()
+            && self.traffic.is_live()
+            && let Some(data) = result.data.as_ref()
+        {
+            self.cache.insert(result.key.clone(), data.clone());
+        }
+    }
+
+    /// Queries every missing option of `items` and plans the cheapest purchases.
+    fn plan_purchases(&mut self, title: String, items: Vec<Arc<Mutex<Item>>>) -> Task<Message> {
+        self.batch_id += 1;
+
+        let mut planner = PlannerState {
+            id: self.batch_id,
+            title,
+            items: vec![],
+            missing: vec![],
+            candidates: BTreeMap::new(),
+            pending: 0,
+            plans: vec![],
+            requests: vec![],
+        };
+        // Items sharing a name, e.g. the pieces of a set, are queried
+        // together once per missing option.
+        let mut batches: Vec<(MarketQuery, ItemOptionType, PlannedItems)> = vec![];
+
+        for (index, item) in items.iter().enumerate() {
+            let item_guard = item.lock().unwrap();
+            let missing: BTreeSet<ItemOptionType> = item_guard
+                .options
+                .lock()
+                .unwrap()
+                .0
+                .iter()
+                .filter(|(_, has_option)| !**has_option)
+                .map(|(option, _)| option.clone())
+                .collect();
+
+            planner.items.push(item_guard.title());
+
+            let Some(item_type) = item_guard.item_type else {
+                continue;
+            };
+
+            for option in missing.iter().filter(|option| option.is_searchable()) {
+                let query = MarketQuery {
+                    names: item_guard.name.iter().cloned().collect(),
+                    item_types: vec![],
+                    options: BTreeSet::from([option.clone()]),
+                    sort: self.settings.default_sort,
+                };
+
+                match batches.iter_mut().find(|(batch, _, _)| *batch == query) {
+                    Some((_, _, batch_items)) => batch_items.push((index, item_type)),
+                    None => batches.push((query, option.clone(), vec![(index, item_type)])),
+                }
+            }
+
+            if !missing.is_empty() {
+                planner.missing.push((index, missing));
+            }
+        }
+
+        let mut tasks = vec![];
+
+        for (mut query, option, batch_items) in batches {
+            query.item_types = batch_items
+                .iter()
+                .map(|(_, item_type)| *item_type)
+                .collect();
+
+            for (name, vars) in query.generate_gql_vars(self.settings.page_size) {
+                let title = format!("{} ({option})", name.unwrap_or_default());
+                let batch_items = batch_items.clone();
+                let option = option.clone();
+                let (task, handle) = self
+                    .market_task(title, vars, planner.id, true)
+                    .map(move |result| {
+                        Message::PlannerResult(batch_items.clone(), option.clone(), result)
+                    })
+                    .abortable();
+
+                tasks.push(task);
+                planner.requests.push(handle.abort_on_drop());
+            }
+        }
+
+        planner.pending = tasks.len();
+        self.planner = Some(planner);
+
+        Task::batch(tasks)
+    }
+
+    /// Runs the cross-set search, one query per name.
+    fn run_market_query(&mut self) -> Task<Message> {
+        self.batch_id += 1;
+
+        let id = self.batch_id;
+        let mut tasks = vec![];
+        let mut requests = vec![];
+
+        for (name, vars) in self
+            .market_query
+            .query
+            .generate_gql_vars(self.settings.page_size)
+        {
+            let title = name.clone().unwrap_or_default();
+            let (task, handle) = self
+                .market_task(title, vars, id, true)
+                .map(move |result| Message::QueryResult(name.clone(), result))
+                .abortable();
+
+            tasks.push(task);
+            requests.push(handle.abort_on_drop());
+        }
+
+        let state = &mut self.market_query;
+        state.id = id;
+        state.pending = tasks.len();
+        state.lots.clear();
+        // Replacing the handles aborts queries of an earlier search.
+        state.requests = requests;
+
+        Task::batch(tasks)
+    }
+
+    /// Collection entries a lot of the searched options would fill, e.g.
+    /// "Dark Knight: Dragon Helm (Zen, DD)".
+    fn collection_fills(&self, name: Option<&str>, lot: &gql::Item) -> Vec<String> {
+        let options = &self.market_query.query.options;
+
+        let fill = |item: &Arc<Mutex<Item>>| {
+            let item_guard = item.lock().unwrap();
+
+            let type_matches = match (item_guard.item_type, lot.type_.as_deref()) {
+                (Some(item_type), Some(lot_type)) => item_type
+                    .gql_type()
+                    .is_none_or(|gql_type| gql_type == lot_type),
+                _ => true,
+            };
+            let name_matches = name.is_none_or(|name| item_guard.is_named(name));
+
+            if !type_matches || !name_matches {
+                return None;
+            }
+
+            let owned = item_guard.options.lock().unwrap();
+            let missing: Vec<&ItemOptionType> = owned
+                .0
+                .iter()
+                .filter(|(option, has_option)| {
+                    !**has_option && (options.is_empty() || options.contains(option))
+                })
+                .map(|(option, _)| option)
+                .collect();
+
+            if missing.is_empty() {
+                return None;
+            }
+
+            if options.is_empty() {
+                Some(item_guard.title())
+            } else {
+                let labels: Vec<String> = missing.iter().map(|option| option.to_string()).collect();
+                Some(format!("{} ({})", item_guard.title(), labels.join(", ")))
+            }
+        };
+
+        let mut fills = vec![];
+
+        for class in self.collections.collection.iter() {
+            let class = class.lock().unwrap();
+
+            for set in class.sets() {
+                for item in set.items.iter() {
+                    if let Some(fill) = fill(item) {
+                        fills.push(format!("{}: {}", class.class_name(), fill));
+                    }
+                }
+            }
+        }
+
+        fills.extend(self.collections.gear.iter().filter_map(fill));
+
+        fills
+    }
+
+    /// Collection item with the given set or item name and type, preferring
+    /// the class on screen since armor sets are shared between classes.
+    fn find_collection_item(
+        &self,
+        name: Option<&str>,
+        item_type: Option<ItemType>,
+    ) -> Option<Arc<Mutex<Item>>> {
+        let matches = |item: &&Arc<Mutex<Item>>| {
+            let item_guard = item.lock().unwrap();
+            item_guard.name.as_deref() == name && item_guard.item_type == item_type
+        };
+
+        if let Some(item) = self.current_items.iter().find(matches) {
+            return Some(item.clone());
+        }
+
+        let current_class = self.current_class.lock().unwrap();
+        let mut classes = vec![current_class.sets().clone()];
+
+        for class in self.collections.collection.iter() {
+            if !Arc::ptr_eq(class, &self.current_class) {
+                classes.push(class.lock().unwrap().sets().clone());
+            }
+        }
+
+        classes
+            .iter()
+            .flatten()
+            .flat_map(|set| set.items.iter())
+            .chain(self.collections.gear.iter())
+            .find(matches)
+            .cloned()
+    }
+
+    fn clear_offers(&mut self, search: &SavedSearch) {
+        // Dropping the handles aborts a search that is still in flight.
+        self.market_requests.clear();
+        self.offer_scores.clear();
+        self.offers.0 = String::new();
+        self.offers.1.clear();
+        self.offer_options.clear();
+        self.offers_fresh = false;
+        self.offers_pending = 0;
+        self.expanded_offer = None;
+        self.pending_purchase = None;
+        self.offers_cached_at = None;
+        self.offers_currency = search.currency.clone();
+        self.last_search = Some(search.clone());
+    }
+
+    pub fn view_collections(&self) -> Container<'_, Message> {
+        let buttons = container(row(vec![
+            widget::pick_list(&Page::ALL[..], Some(self.page), Message::ChangePage)
+                .placeholder("Wybierz klasę")
+                .into(),
+            widget::pick_list(
+                self.set_names(),
+                self.set_selected.clone(),
+                Message::ChangeSet,
+            )
+            .placeholder("Wybierz set")
+            .into(),
+            widget::button("Wyczyść oferty")
+                .on_press(Message::ClearOffers)
+                .into(),
+            widget::button("Zapisz kolekcje")
+                .on_press_maybe(self.read_only.is_none().then_some(Message::SaveCollections))
+                .into(),
+            widget::button("Eksportuj kolekcje")
+                .on_press(Message::ExportCollections)
+                .into(),
+            widget::button("Plan zakupu setu")
+                .on_press_maybe(
+                    (!self.current_items.is_empty())
+                        .then_some(Message::PlanPurchases(PlanScope::Set)),
+                )
+                .into(),
+            widget::button("Plan zakupu klasy")
+                .on_press(Message::PlanPurchases(PlanScope::Class))
+                .into(),
+            widget::button("Wyszukiwarka")
+                .on_press(Message::ChangeScreen(Screen::MarketQuery))
+                .into(),
+            widget::checkbox("Siatka klasy", self.show_class_grid)
+                .on_toggle(Message::ShowClassGrid)
+                .into(),
+            widget::tooltip(
+                widget::slider(0.2..=0.7, self.offers_split, Message::ChangeOffersSplit)
+                    .step(0.05)
+                    .width(Length::Fixed(100.0)),
+                "Szerokość ofert",
+                widget::tooltip::Position::Bottom,
+            )
+            .into(),
+            widget::button("Ustawienia")
+                .on_press(Message::ChangeScreen(Screen::Settings))
+                .into(),
+            widget::button("Skróty (F1)")
+                .on_press(Message::Shortcut(Shortcut::ToggleCheatSheet))
+                .into(),
+            self.view_session(),
+        ]))
+        .padding(10)
+        .center_x(Length::Fill);
+
+        let mut content = widget::column!()
+            .push(buttons)
+            .push(self.view_set_search())
+            .push(horizontal_rule(Pixels::from(2)));
+
+        let mut item_parts = widget::column!().spacing(15);
+
+        for (index, item) in self.current_items.iter().enumerate() {
+            let item_guard = item.lock().unwrap();
+
+            let mut row = widget::row!().spacing(10).width(Length::Fill);
+
+            let item_name = item_guard.title();
+
+            row = row.push(widget::container(
+                widget::button(widget::text!("{}", item_name).align_x(Alignment::Center))
+                    .on_press(Message::SearchMarket(item.clone()))
+                    .height(Length::Fixed(200.0))
+                    .width(Length::Fixed(150.0)),
+            ));
+
+            let wanted = item_guard.search_options().unwrap_or_default();
+            let options = item_guard.options.lock().unwrap();
+            let mut col = widget::column!();
+
+            for (option, has_option) in options.0.clone() {
+                let is_wanted = wanted.0.get(&option).copied().unwrap_or(false);
+
+                let mut wanted_checkbox = widget::checkbox("", is_wanted);
+
+                if item_guard.search_mode == SearchMode::Custom && option.is_searchable() {
+                    let item = item.clone();
+                    let option = option.clone();
+
+                    wanted_checkbox = wanted_checkbox.on_toggle(move |enabled| {
+                        Message::UpdateWanted(item.clone(), option.clone(), enabled)
+                    });
+                }
+
+                col = col.push(
+                    widget::container(
+                        widget::row!().push(wanted_checkbox).push(
+                            widget::checkbox(option.to_string(), has_option)
+                                .on_toggle_maybe(self.read_only.is_none().then_some(
+                                    move |enabled| {
+                                        let item_clone = item.clone();
+                                        Message::UpdateItem(item_clone, option.clone(), enabled)
+                                    },
+                                ))
+                                .spacing(10),
+                        ),
+                    )
+                    .height(Length::Fixed(30.0)),
+                );
+            }
+            row = row.push(widget::container(col).center_y(Length::Fixed(200.0)));
+            row = row.push(
+                widget::container(
+                    widget::column!()
                         .spacing(10)
-                        .push(widget::text("Gear Score"))
                         .push(
-                            widget::text(item.gear_score.unwrap_or_default())
-                                .font(Font::MONOSPACE)
-                                .color(Color::from_rgb(0.8, 0.2, 0.2)),
+                            widget::row!()
+                                .spacing(10)
+                                .align_y(Alignment::Center)
+                                .push(widget::text("Szukaj opcji"))
+                                .push(widget::pick_list(
+                                    &SearchMode::ALL[..],
+                                    Some(item_guard.search_mode),
+                                    {
+                                        let item = item.clone();
+                                        move |mode| Message::ChangeSearchMode(item.clone(), mode)
+                                    },
+                                )),
+                        )
+                        .push(
+                            widget::button("Zapisz wyszukiwanie")
+                                .on_press(Message::SaveSearch(item.clone())),
                         ),
+                )
+                .center_y(Length::Fixed(200.0)),
+            );
+            let focused = self.focused_item == Some(index);
+
+            item_parts = item_parts.push(widget::container(row).padding(5).style(
+                move |theme: &Theme| {
+                    if focused {
+                        container::Style::default().border(Border {
+                            color: theme.extended_palette().primary.base.color,
+                            width: 2.0,
+                            radius: 4.0.into(),
+                        })
+                    } else {
+                        container::Style::default()
+                    }
+                },
+            ));
+        }
+
+        let offers_container = self.view_offers();
+
+        // The saved searches keep a sixth of the width, the rest is split.
+        const SEARCHES_PORTION: u16 = 10;
+        let offers_portion = (self.offers_split * 50.0).round() as u16;
+        let items_portion = 50 - offers_portion;
+
+        let row = widget::row!()
+            .spacing(20)
+            .push(
+                widget::scrollable(
+                    widget::column!()
+                        .spacing(20)
+                        .push(self.view_saved_searches())
+                        .push(self.view_purchases()),
+                )
+                .width(Length::FillPortion(SEARCHES_PORTION))
+                .spacing(16),
+            )
+            .push(
+                widget::scrollable(if self.show_class_grid {
+                    self.view_class_grid()
+                } else {
+                    item_parts.into()
+                })
+                .width(Length::FillPortion(items_portion))
+                .spacing(16),
+            )
+            .push(
+                widget::scrollable(offers_container)
+                    .width(Length::FillPortion(offers_portion))
+                    .spacing(16),
+            );
+
+        content = content.push(row);
+
+        widget::container(content)
+            .padding(30)
+            .align_x(Horizontal::Center)
+    }
+
+    /// Login state and the session token field.
+    fn view_session(&self) -> Element<'_, Message> {
+        if self.session.is_logged_in() {
+            widget::row!()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(widget::text("Zalogowano").style(widget::text::success))
+                .push(widget::button("Wyloguj").on_press(Message::Logout))
+                .into()
+        } else {
+            widget::row!()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(
+                    widget::text_input("Token sesji", &self.session_input)
+                        .secure(true)
+                        .on_input(Message::SessionInput)
+                        .on_submit(Message::Login)
+                        .width(Length::Fixed(200.0)),
+                )
+                .push(widget::button("Zaloguj").on_press_maybe(
+                    (!self.session_input.trim().is_empty()).then_some(Message::Login),
+                ))
+                .into()
+        }
+    }
+
+    /// Every set of the current class with one cell per piece, each showing
+    /// which options are owned.
+    fn view_class_grid(&self) -> Element<'_, Message> {
+        const SET_COLUMN: f32 = 140.0;
+        const PIECE_COLUMN: f32 = 130.0;
+
+        let mut header = widget::row!()
+            .spacing(10)
+            .push(widget::text("Set").width(Length::Fixed(SET_COLUMN)));
+
+        for item_type in ItemType::ALL
+            .iter()
+            .filter(|item_type| item_type.category() == ItemCategory::Armor)
+        {
+            header =
+                header.push(widget::text(item_type.to_string()).width(Length::Fixed(PIECE_COLUMN)));
+        }
+
+        let mut grid = widget::column!().spacing(6).push(header);
+
+        for set in self.set_options.iter() {
+            let mut row = widget::row!()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(widget::text(set.set_string.clone()).width(Length::Fixed(SET_COLUMN)));
+
+            for item in set.items.iter() {
+                row = row.push(
+                    widget::container(self.view_grid_cell(item)).width(Length::Fixed(PIECE_COLUMN)),
+                );
+            }
+
+            grid = grid.push(row);
+        }
+
+        grid.into()
+    }
+
+    /// Ownership dots of one piece that toggle on click, and an owned count
+    /// that searches the market for the piece.
+    fn view_grid_cell(&self, item: &Arc<Mutex<Item>>) -> Element<'_, Message> {
+        let item_guard = item.lock().unwrap();
+        let options = item_guard.options.lock().unwrap();
+
+        let mut dots = widget::row!().spacing(3);
+
+        for (option, has_option) in options.0.iter() {
+            let owned = *has_option;
+            let dot = widget::container(widget::Space::new(10, 10)).style(move |theme: &Theme| {
+                let palette = theme.extended_palette();
+                let color = if owned {
+                    palette.success.base.color
+                } else {
+                    palette.background.strong.color
+                };
+
+                container::Style::default()
+                    .background(color)
+                    .border(Border::default().rounded(5))
+            });
+
+            let toggle = self
+                .read_only
+                .is_none()
+                .then(|| Message::UpdateItem(item.clone(), option.clone(), !has_option));
+
+            dots = dots.push(widget::tooltip(
+                widget::button(dot)
+                    .padding(1)
+                    .style(widget::button::text)
+                    .on_press_maybe(toggle),
+                widget::text(option.spec().label).size(12),
+                widget::tooltip::Position::Top,
+            ));
+        }
+
+        let owned = options.0.values().filter(|has_option| **has_option).count();
+
+        widget::column!()
+            .spacing(2)
+            .push(dots)
+            .push(
+                widget::button(widget::text(format!("{owned}/{}", options.0.len())).size(12))
+                    .padding(0)
+                    .style(widget::button::text)
+                    .on_press(Message::SearchMarket(item.clone())),
+            )
+            .into()
+    }
+
+    /// Type-ahead search over the sets of every class.
+    fn view_set_search(&self) -> Element<'_, Message> {
+        let mut col = widget::column!().spacing(5).padding([0, 10]).push(
+            widget::text_input("Szukaj setu we wszystkich klasach", &self.set_query)
+                .on_input(Message::SetQuery)
+                .on_submit(Message::JumpToFirstSet)
+                .width(Length::Fixed(400.0)),
+        );
+
+        for (set, pages) in self.set_matches() {
+            let mut row = widget::row!()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(widget::text(set.clone()).width(Length::Fixed(150.0)));
+
+            for page in pages {
+                row = row.push(
+                    widget::button(widget::text(page.to_string()).size(12))
+                        .on_press(Message::JumpToSet(page, set.clone())),
                 );
+            }
+
+            col = col.push(row);
+        }
+
+        col.into()
+    }
+
+    /// Editor of the application settings.
+    fn view_settings(&self) -> Container<'_, Message> {
+        let Some(draft) = self.settings_draft.as_ref() else {
+            return widget::container(widget::column!());
+        };
+
+        let edited = |apply: fn(&mut SettingsDraft, String)| {
+            let draft = draft.clone();
+            move |value| {
+                let mut draft = draft.clone();
+                apply(&mut draft, value);
+                Message::SettingsEdited(draft)
+            }
+        };
+        let field = |label: &'static str, input: Element<'static, Message>| {
+            widget::row!()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(widget::text(label).width(Length::Fixed(280.0)))
+                .push(input)
+        };
+
+        let form = widget::column!()
+            .spacing(12)
+            .max_width(800)
+            .push(widget::text("Ustawienia").size(24))
+            .push(field(
+                "Motyw",
+                widget::pick_list(ThemeChoice::all(), Some(draft.theme.clone()), {
+                    let draft = draft.clone();
+                    move |theme| {
+                        Message::SettingsEdited(SettingsDraft {
+                            theme,
+                            ..draft.clone()
+                        })
+                    }
+                })
+                .into(),
+            ))
+            .push(field(
+                "Domyślne sortowanie",
+                widget::pick_list(&SortOrder::ALL[..], Some(draft.default_sort), {
+                    let draft = draft.clone();
+                    move |default_sort| {
+                        Message::SettingsEdited(SettingsDraft {
+                            default_sort,
+                            ..draft.clone()
+                        })
+                    }
+                })
+                .into(),
+            ))
+            .push(field(
+                "Adres API",
+                widget::text_input(DEFAULT_ENDPOINT, &draft.endpoint)
+                    .on_input(edited(|draft, endpoint| draft.endpoint = endpoint))
+                    .into(),
+            ))
+            .push(field(
+                "Adres oferty ({id} to numer oferty)",
+                widget::text_input(DEFAULT_LOT_URL_TEMPLATE, &draft.lot_url_template)
+                    .on_input(edited(|draft, template| draft.lot_url_template = template))
+                    .into(),
+            ))
+            .push(field(
+                "Język odpowiedzi rynku",
+                widget::text_input(DEFAULT_LANGUAGE, &draft.language)
+                    .on_input(edited(|draft, language| draft.language = language))
+                    .into(),
+            ))
+            .push(field(
+                "Ofert na zapytanie",
+                widget::text_input("", &draft.page_size)
+                    .on_input(edited(|draft, value| draft.page_size = value))
+                    .into(),
+            ))
+            .push(field(
+                "Ważność pamięci podręcznej (s)",
+                widget::text_input("", &draft.cache_ttl_secs)
+                    .on_input(edited(|draft, value| draft.cache_ttl_secs = value))
+                    .into(),
+            ))
+            .push(field(
+                "Równoczesne zapytania",
+                widget::text_input("", &draft.max_concurrent_requests)
+                    .on_input(edited(|draft, value| draft.max_concurrent_requests = value))
+                    .into(),
+            ))
+            .push(field(
+                "Zapytań na minutę",
+                widget::text_input("", &draft.max_requests_per_minute)
+                    .on_input(edited(|draft, value| draft.max_requests_per_minute = value))
+                    .into(),
+            ))
+            .push(field(
+                "Powiadomienia pulpitu",
+                widget::checkbox("", draft.notifications)
+                    .on_toggle({
+                        let draft = draft.clone();
+                        move |notifications| {
+                            Message::SettingsEdited(SettingsDraft {
+                                notifications,
+                                ..draft.clone()
+                            })
+                        }
+                    })
+                    .into(),
+            ))
+            .push(field(
+                "Powiadom o ofertach tańszych niż (KOD=cena, …)",
+                widget::text_input("np. ZEN=1000000", &draft.alert_prices)
+                    .on_input(edited(|draft, value| draft.alert_prices = value))
+                    .into(),
+            ))
+            .push(
+                widget::text(
+                    "Zmienne środowiskowe DREAM_COLLECTIONS_* mają pierwszeństwo przed ustawieniami.",
+                )
+                .size(12)
+                .style(widget::text::secondary),
+            )
+            .push_maybe(
+                self.settings_error
+                    .as_ref()
+                    .map(|err| widget::text(err).style(widget::text::danger)),
+            )
+            .push(
+                widget::row!()
+                    .spacing(10)
+                    .push(widget::button("Zapisz").on_press(Message::ApplySettings))
+                    .push(
+                        widget::button("Anuluj")
+                            .on_press(Message::ChangeScreen(Screen::Collections)),
+                    )
+                    .push(
+                        widget::button("Przywróć domyślne")
+                            .on_press(Message::SettingsEdited(SettingsDraft::from(
+                                &Settings::default(),
+                            ))),
+                    ),
+            );
+
+        widget::container(widget::scrollable(form)).padding(30)
+    }
+
+    /// Lots marked as bought, newest first.
+    pub fn view_purchases(&self) -> Container<'_, Message> {
+        let mut col = widget::column!()
+            .spacing(10)
+            .push(widget::text("Historia zakupów").size(20));
+
+        for purchase in self.purchases.purchases.iter().rev() {
+            let options: Vec<String> = purchase
+                .options
+                .iter()
+                .map(|option| option.to_string())
+                .collect();
+            let prices: Vec<String> = purchase
+                .prices
+                .iter()
+                .map(|(currency, value)| format!("{value} {currency}"))
+                .collect();
+
+            col = col.push(
+                widget::column!()
+                    .spacing(2)
+                    .push(widget::text(format!(
+                        "{} - {}",
+                        format_date(purchase.purchased_at),
+                        purchase.item
+                    )))
+                    .push(widget::text(options.join(", ")).size(12))
+                    .push(
+                        widget::text(prices.join(" / "))
+                            .size(12)
+                            .font(Font::MONOSPACE),
+                    ),
+            );
+        }
+
+        widget::container(col)
+    }
 
-                let mut row = widget::row!().spacing(10);
+    pub fn view_saved_searches(&self) -> Container<'_, Message> {
+        let mut col = widget::column!().spacing(10);
 
-                for price in item.prices.iter() {
-                    let currency = &price.currency;
-                    let value = price.value.unwrap_or_default();
+        col = col.push(widget::text("Zapisane wyszukiwania").size(20));
 
-                    let currency_title = currency.title.as_deref().unwrap_or("Unknown");
+        for (index, search) in self.searches.searches.iter().enumerate() {
+            if let Some((editing_index, editing)) = self.editing_search.as_ref()
+                && *editing_index == index
+            {
+                col = col.push(view_search_editor(editing));
+                continue;
+            }
 
-                    row = row.push(
-                        widget::column!()
+            col = col.push(
+                widget::column!()
+                    .spacing(5)
+                    .push(widget::text(search.name.clone()))
+                    .push(
+                        widget::row!()
+                            .spacing(5)
+                            .push(widget::button("Szukaj").on_press(Message::RunSavedSearch(index)))
+                            .push(
+                                widget::button("Edytuj").on_press(Message::EditSavedSearch(index)),
+                            )
                             .push(
-                                widget::text(currency_title).color(Color::from_rgb(0.2, 0.6, 0.8)),
+                                widget::button("Duplikuj")
+                                    .on_press(Message::DuplicateSavedSearch(index)),
                             )
                             .push(
-                                widget::text(format!("{value}"))
-                                    .font(Font::MONOSPACE)
-                                    .size(20),
+                                widget::button("Usuń").on_press(Message::DeleteSavedSearch(index)),
                             ),
-                    );
+                    ),
+            );
+        }
+
+        widget::container(col)
+    }
+
+    /// Cross-set search form next to its results.
+    pub fn view_market_query(&self) -> Container<'_, Message> {
+        let state = &self.market_query;
+        let query = &state.query;
+
+        let buttons = container(
+            widget::row!()
+                .spacing(10)
+                .push(
+                    widget::button("Kolekcje").on_press(Message::ChangeScreen(Screen::Collections)),
+                )
+                .push(self.view_session()),
+        )
+        .padding(10)
+        .center_x(Length::Fill);
+
+        let mut item_types = widget::row!().spacing(10);
+
+        for item_type in ItemType::ALL {
+            item_types = item_types.push(
+                widget::checkbox(item_type.to_string(), query.item_types.contains(&item_type))
+                    .on_toggle(move |enabled| Message::QueryItemType(item_type, enabled)),
+            );
+        }
+
+        let mut options = widget::row!().spacing(10);
+
+        for spec in OPTION_SCHEMA.iter().filter(|spec| spec.filter.is_some()) {
+            options = options.push(
+                widget::checkbox(spec.label, query.options.contains(&spec.option))
+                    .on_toggle(move |enabled| Message::QueryOption(spec.option.clone(), enabled)),
+            );
+        }
+
+        let form = widget::column!()
+            .spacing(15)
+            .push(widget::text("Nazwa"))
+            .push(
+                widget::text_input("Np. Dragon, Legendary Staff", &state.names)
+                    .on_input(Message::QueryNames)
+                    .on_submit(Message::RunQuery),
+            )
+            .push(widget::text("Typ przedmiotu"))
+            .push(item_types.wrap())
+            .push(widget::text("Opcje"))
+            .push(options.wrap())
+            .push(
+                widget::row!()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(widget::text("Sortowanie"))
+                    .push(widget::pick_list(
+                        &SortOrder::ALL[..],
+                        Some(query.sort),
+                        Message::QuerySort,
+                    )),
+            )
+            .push(widget::button("Szukaj").on_press(Message::RunQuery));
+
+        let mut results = widget::column!()
+            .spacing(8)
+            .push(view_option_chips("Wymagane opcje:", &query.options));
+
+        if state.pending > 0 {
+            results = results.push(widget::text(format!(
+                "Pozostało zapytań: {}",
+                state.pending
+            )));
+        } else if state.id != 0 && state.lots.is_empty() {
+            results = results.push(widget::text("Brak ofert"));
+        }
+
+        for found in state.lots.iter() {
+            let lot = &found.lot;
+
+            let mut header = widget::row!()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(widget::text(lot_title(lot)))
+                .push(widget::text("Gear Score"))
+                .push(
+                    widget::text(lot.gear_score.unwrap_or_default())
+                        .font(Font::MONOSPACE)
+                        .style(widget::text::danger),
+                );
+
+            if let Some(name) = found.name.as_ref() {
+                header = header.push(view_badge(name.as_str(), |palette| palette.primary.base));
+            }
+
+            if lot.is_mine.unwrap_or(false) {
+                header = header.push(view_badge("Moja oferta", |palette| palette.success.base));
+            }
+
+            let mut prices = widget::row!().spacing(10);
+
+            for price in lot.prices.iter() {
+                prices = prices.push(
+                    widget::text(format!(
+                        "{}: {}",
+                        price.currency.title.as_deref().unwrap_or("Unknown"),
+                        price.value.unwrap_or_default()
+                    ))
+                    .font(Font::MONOSPACE),
+                );
+            }
+
+            let mut entry = widget::column!().spacing(6).push(header).push(prices);
+
+            if found.fills.is_empty() {
+                entry = entry
+                    .push(widget::text("Nie uzupełnia kolekcji").style(widget::text::secondary));
+            } else {
+                entry = entry.push(widget::text("Uzupełnia:"));
+
+                for fill in found.fills.iter() {
+                    entry = entry.push(widget::text(format!("• {fill}")).size(14));
                 }
-                colu = colu.push(row);
+            }
+
+            if let Some(id) = lot.id.clone() {
+                entry = entry
+                    .push(widget::button("Otwórz w przeglądarce").on_press(Message::OpenLot(id)));
+            }
+
+            results = results.push(entry.push(horizontal_rule(Pixels::from(1))));
+        }
+
+        let row = widget::row!()
+            .spacing(20)
+            .padding(10)
+            .push(widget::scrollable(form).width(Length::FillPortion(1)))
+            .push(widget::scrollable(results).width(Length::FillPortion(1)));
+
+        widget::container(
+            widget::column!()
+                .push(buttons)
+                .push(horizontal_rule(Pixels::from(2)))
+                .push(row),
+        )
+    }
+
+    /// Cheapest purchases per currency, or progress while queries run.
+    fn view_planner<'a>(&'a self, planner: &'a PlannerState) -> Element<'a, Message> {
+        let mut col = widget::column!().spacing(8).push(
+            widget::row!()
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .push(widget::text(format!("Plan zakupu: {}", planner.title)).size(20))
+                .push(widget::button("Zamknij").on_press(Message::ClosePlanner)),
+        );
+
+        if planner.pending > 0 {
+            return col
+                .push(widget::text(format!(
+                    "Pozostało zapytań: {}",
+                    planner.pending
+                )))
+                .into();
+        }
+
+        if planner.missing.is_empty() {
+            return col.push(widget::text("Wszystkie opcje zebrane")).into();
+        }
+
+        if planner.plans.is_empty() {
+            return col.push(widget::text("Brak ofert")).into();
+        }
+
+        for plan in planner.plans.iter() {
+            col = col.push(widget::text(plan.currency.clone()).size(18));
+
+            for line in plan.lines.iter() {
+                let options = line
+                    .options
+                    .iter()
+                    .map(|option| option.spec().label)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                col = col.push(
+                    widget::button(widget::text(format!(
+                        "{}: {} - {}",
+                        planner.items[line.item], options, line.cost
+                    )))
+                    .style(widget::button::text)
+                    .on_press(Message::OpenLot(line.lot_id.clone())),
+                );
+            }
+
+            for (item, option) in plan.uncovered.iter() {
+                let reason = if option.is_searchable() {
+                    "brak ofert"
+                } else {
+                    "rynek nie wyszukuje tej opcji"
+                };
+
+                col = col.push(
+                    widget::text(format!("{}: {option} - {reason}", planner.items[*item]))
+                        .style(widget::text::secondary),
+                );
+            }
+
+            col = col.push(widget::text(format!("Razem: {}", plan.total)).font(Font::MONOSPACE));
+        }
+
+        col.push(widget::horizontal_rule(Pixels::from(2))).into()
+    }
+
+    pub fn view_offers(&self) -> Container<'_, Message> {
+        let mut col = widget::column!();
+
+        if let Some(planner) = self.planner.as_ref() {
+            col = col.push(self.view_planner(planner));
+        }
+
+        col = col.push(widget::text(self.offers.0.clone()).size(24));
+
+        if let Some(path) = self.pending_export.as_ref() {
+            col = col.push(
+                widget::row!()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(widget::text(format!(
+                        "Plik {} już istnieje. Nadpisać?",
+                        path.display()
+                    )))
+                    .push(widget::button("Nadpisz").on_press(Message::ConfirmExport))
+                    .push(
+                        widget::button("Anuluj")
+                            .style(widget::button::secondary)
+                            .on_press(Message::CancelExport),
+                    ),
+            );
+        }
+
+        match &self.traffic {
+            Traffic::Live => {}
+            Traffic::Record(dir) => {
+                col = col.push(widget::text(format!("Nagrywanie do {}", dir.display())));
+            }
+            Traffic::Replay(dir) => {
+                col = col.push(widget::text(format!("Odtwarzanie z {}", dir.display())));
+            }
+        }
+
+        if self.last_search.is_some() {
+            let mut status = widget::row!().spacing(10).align_y(Alignment::Center);
+
+            if let Some(cached_at) = self.offers_cached_at {
+                status = status.push(widget::text(format!(
+                    "Z pamięci podręcznej, {}",
+                    format_age(cached_at)
+                )));
+            }
+
+            col = col.push(status.push(widget::button("Odśwież").on_press(Message::RefreshOffers)));
+        }
+
+        col = col.push(
+            widget::row!()
+                .spacing(20)
+                .push(
+                    widget::checkbox("Ukryj moje oferty", self.hide_own_lots)
+                        .on_toggle(Message::HideOwnLots),
+                )
+                .push(
+                    widget::checkbox(
+                        "Pomijaj moje oferty w najniższej cenie",
+                        self.exclude_own_from_cheapest,
+                    )
+                    .on_toggle(Message::ExcludeOwnFromCheapest),
+                )
+                .push(
+                    widget::checkbox("Sortuj według okazji", self.sort_by_deal)
+                        .on_toggle(Message::SortByDeal),
+                ),
+        );
+
+        for (currency, value) in self.cheapest_prices() {
+            col = col.push(widget::text(format!(
+                "Najniższa cena ({currency}): {value}"
+            )));
+        }
+
+        let mut lots: Vec<(usize, &gql::Item)> = self.offers.1.iter().enumerate().collect();
+
+        // The queries of one search are merged, so the search's order is
+        // applied again, lots carrying more wanted options first among equals.
+        if let Some(search) = self.last_search.as_ref() {
+            lots.sort_by(|(a_index, a), (b_index, b)| {
+                let covered =
+                    |index: &usize| self.offer_options.get(*index).map_or(0, BTreeSet::len);
+
+                search
+                    .sort
+                    .compare(a, b, self.offers_currency.as_deref())
+                    .then_with(|| covered(b_index).cmp(&covered(a_index)))
+            });
+        }
+
+        if self.sort_by_deal {
+            lots.sort_by_key(|(index, _)| {
+                std::cmp::Reverse(
+                    self.offer_scores
+                        .get(*index)
+                        .cloned()
+                        .flatten()
+                        .map(|s| s.score),
+                )
+            });
+        }
+
+        for (index, item) in lots {
+            let is_mine = item.is_mine.unwrap_or(false);
+
+            if is_mine && self.hide_own_lots {
+                continue;
+            }
+
+            col = col.push(
+                widget::container({
+                    let mut colu = widget::column!().spacing(8);
 
-                colu = colu.push(widget::horizontal_rule(Pixels::from(1)));
+                    let mut header = widget::row!()
+                        .spacing(10)
+                        .push(widget::text(lot_title(item)))
+                        .push(widget::text("Gear Score"))
+                        .push(
+                            widget::text(item.gear_score.unwrap_or_default())
+                                .font(Font::MONOSPACE)
+                                .style(widget::text::danger),
+                        );
+
+                    if is_mine {
+                        header =
+                            header.push(view_badge("Moja oferta", |palette| palette.success.base));
+                    }
+
+                    if item.has_pending_counter_offer.unwrap_or(false) {
+                        header =
+                            header.push(view_badge("Kontroferta", |palette| palette.danger.weak));
+                    }
+
+                    if let Some(Some(deal)) = self.offer_scores.get(index) {
+                        let tone: Tone = match deal.score {
+                            70.. => |palette| palette.success.base,
+                            40..70 => |palette| palette.primary.base,
+                            _ => |palette| palette.secondary.base,
+                        };
+
+                        header = header.push(view_badge(format!("Okazja {}", deal.score), tone));
+                    }
+
+                    colu = colu.push(header);
+
+                    if let Some(options) = self.offer_options.get(index) {
+                        colu = colu.push(view_option_chips("Znaleziono dla opcji:", options));
+                    }
+
+                    let mut row = widget::row!().spacing(10);
 
-                colu
-            }));
+                    for price in item.prices.iter() {
+                        let currency = &price.currency;
+                        let value = price.value.unwrap_or_default();
+
+                        let currency_title = currency.title.as_deref().unwrap_or("Unknown");
+
+                        row = row.push(
+                            widget::column!()
+                                .push(widget::text(currency_title).style(widget::text::primary))
+                                .push(
+                                    widget::text(format!("{value}"))
+                                        .font(Font::MONOSPACE)
+                                        .size(20),
+                                ),
+                        );
+                    }
+                    colu = colu.push(row);
+
+                    let mut lot = widget::column!().spacing(8).push(
+                        widget::button(colu)
+                            .style(widget::button::text)
+                            .width(Length::Fill)
+                            .on_press(Message::ToggleOffer(index)),
+                    );
+
+                    if self.expanded_offer == Some(index) {
+                        lot = lot.push(self.view_offer_details(index, item));
+                    }
+
+                    lot.push(widget::horizontal_rule(Pixels::from(1)))
+                })
+                .style(move |theme: &Theme| {
+                    if is_mine {
+                        container::Style::default().background(Color {
+                            a: 0.15,
+                            ..theme.extended_palette().success.base.color
+                        })
+                    } else {
+                        container::Style::default()
+                    }
+                }),
+            );
         }
 
         widget::container(col)
     }
+
+    /// Lowest listed price per currency title.
+    fn cheapest_prices(&self) -> BTreeMap<String, i64> {
+        let mut cheapest = BTreeMap::new();
+
+        for item in self.offers.1.iter() {
+            let is_mine = item.is_mine.unwrap_or(false);
+
+            if is_mine && (self.hide_own_lots || self.exclude_own_from_cheapest) {
+                continue;
+            }
+
+            for price in item.prices.iter() {
+                let (Some(title), Some(value)) = (price.currency.title.clone(), price.value) else {
+                    continue;
+                };
+
+                cheapest
+                    .entry(title)
+                    .and_modify(|min: &mut i64| *min = (*min).min(value))
+                    .or_insert(value);
+            }
+        }
+
+        cheapest
+    }
+
+    /// All metadata of an expanded lot.
+    fn view_offer_details<'a>(&self, index: usize, item: &'a gql::Item) -> Element<'a, Message> {
+        let yes_no = |value: Option<bool>| match value {
+            Some(true) => "Tak",
+            Some(false) => "Nie",
+            None => "-",
+        };
+
+        let detail = |label: &'a str, value: String| {
+            widget::row!()
+                .spacing(10)
+                .push(widget::text(label).width(Length::Fixed(180.0)))
+                .push(widget::text(value).font(Font::MONOSPACE))
+        };
+
+        let mut card = widget::column!()
+            .spacing(6)
+            .padding(10)
+            .push(detail("ID", item.id.clone().unwrap_or_default()))
+            .push(detail("Źródło", item.source.clone().unwrap_or_default()))
+            .push(detail("Typ", item.type_.clone().unwrap_or_default()))
+            .push(detail("Moja oferta", yes_no(item.is_mine).to_string()))
+            .push(detail(
+                "Oczekująca kontroferta",
+                yes_no(item.has_pending_counter_offer).to_string(),
+            ));
+
+        if let Some(Some(deal)) = self.offer_scores.get(index) {
+            let history = deal
+                .history_ratio
+                .map(|ratio| format!(", {ratio:.2}× wcześniejszych cen"))
+                .unwrap_or_default();
+
+            card = card.push(detail(
+                "Ocena okazji",
+                format!(
+                    "{} ({:.2}× mediany{history}, opcje {}/{})",
+                    deal.score, deal.peer_ratio, deal.covered, deal.wanted
+                ),
+            ));
+        }
+
+        for currency in item.currencies.iter().flatten() {
+            card = card.push(detail(
+                "Waluta",
+                format!(
+                    "{} ({}), dostępna dla ofert: {}",
+                    currency.title.as_deref().unwrap_or("Unknown"),
+                    currency.code.as_deref().unwrap_or("-"),
+                    yes_no(currency.is_available_for_lots)
+                ),
+            ));
+        }
+
+        card = card.push(
+            widget::row!()
+                .spacing(10)
+                .push(
+                    widget::button("Otwórz w przeglądarce")
+                        .on_press_maybe(item.id.clone().map(Message::OpenLot)),
+                )
+                .push(
+                    widget::button("Kupiłem").on_press_maybe(
+                        (self.read_only.is_none() && self.pending_purchase.is_none())
+                            .then_some(Message::MarkPurchased(index)),
+                    ),
+                ),
+        );
+
+        if let Some(pending) = self
+            .pending_purchase
+            .as_ref()
+            .filter(|pending| pending.index == index)
+        {
+            card = card.push(self.view_pending_purchase(pending));
+        }
+
+        widget::container(card).into()
+    }
+
+    /// Asks which of the item's missing options the bought lot carries.
+    fn view_pending_purchase<'a>(&self, pending: &PendingPurchase) -> Element<'a, Message> {
+        let item_guard = pending.item.lock().unwrap();
+        let owned = item_guard.options.lock().unwrap();
+
+        let mut col = widget::column!().spacing(6).push(widget::text(format!(
+            "Które opcje ma kupiony przedmiot ({})?",
+            item_guard.title()
+        )));
+
+        for (option, has_option) in owned.0.iter() {
+            if *has_option {
+                continue;
+            }
+
+            let option = option.clone();
+            col = col.push(
+                widget::checkbox(option.spec().label, pending.options.contains(&option)).on_toggle(
+                    move |carried| Message::TogglePurchaseOption(option.clone(), carried),
+                ),
+            );
+        }
+
+        col.push(
+            widget::row!()
+                .spacing(10)
+                .push(widget::button("Zapisz zakup").on_press_maybe(
+                    (!pending.options.is_empty()).then_some(Message::ConfirmPurchase),
+                ))
+                .push(
+                    widget::button("Anuluj")
+                        .style(widget::button::secondary)
+                        .on_press(Message::CancelPurchase),
+                ),
+        )
+        .into()
+    }
+}
+
+/// Keyboard shortcuts listed over the whole window.
+fn view_cheat_sheet<'a>() -> Element<'a, Message> {
+    let mut keys = widget::column!().spacing(8);
+
+    for (key, action) in CHEAT_SHEET {
+        keys = keys.push(
+            widget::row!()
+                .spacing(20)
+                .push(
+                    widget::text(*key)
+                        .font(Font::MONOSPACE)
+                        .width(Length::Fixed(180.0)),
+                )
+                .push(widget::text(*action)),
+        );
+    }
+
+    let card = widget::container(
+        widget::column!()
+            .spacing(15)
+            .push(widget::text("Skróty klawiszowe").size(24))
+            .push(keys)
+            .push(
+                widget::button("Zamknij").on_press(Message::Shortcut(Shortcut::ToggleCheatSheet)),
+            ),
+    )
+    .padding(20)
+    .style(container::rounded_box);
+
+    widget::center(card)
+        .style(|_| container::Style::default().background(Color::from_rgba(0.0, 0.0, 0.0, 0.6)))
+        .into()
+}
+
+/// Item type of a lot, e.g. "helm".
+fn lot_title(lot: &gql::Item) -> String {
+    lot.type_.clone().unwrap_or_default()
+}
+
+/// Excellent options the market filtered on, after `label`. Lots do not list
+/// their own options, so these are all that is known about a lot.
+fn view_option_chips<'a>(
+    label: &'a str,
+    options: &BTreeSet<ItemOptionType>,
+) -> Element<'a, Message> {
+    if options.is_empty() {
+        return widget::row!().into();
+    }
+
+    let mut chips = widget::row!().spacing(5).push(widget::text(label).size(12));
+
+    for option in options {
+        chips = chips.push(view_badge(option.spec().label, |palette| {
+            palette.success.base
+        }));
+    }
+
+    chips.wrap().into()
+}
+
+/// Picks the colors of a badge from the theme palette.
+type Tone = fn(&palette::Extended) -> palette::Pair;
+
+/// Small colored label shown next to a lot's gear score.
+fn view_badge<'a>(label: impl widget::text::IntoFragment<'a>, tone: Tone) -> Element<'a, Message> {
+    widget::container(widget::text(label).size(12))
+        .padding([2, 6])
+        .style(move |theme: &Theme| {
+            let pair = tone(theme.extended_palette());
+
+            container::Style::default()
+                .background(pair.color)
+                .color(pair.text)
+        })
+        .into()
+}
+
+/// Form for editing a saved search in place.
+fn view_search_editor(search: &SavedSearch) -> Element<'_, Message> {
+    let edited = |apply: fn(&mut SavedSearch, String)| {
+        let search = search.clone();
+        move |value| {
+            let mut search = search.clone();
+            apply(&mut search, value);
+            Message::SavedSearchEdited(search)
+        }
+    };
+
+    let mut options = widget::column!();
+
+    for (option, has_option) in search.options.0.clone() {
+        let search = search.clone();
+        let searchable = option.is_searchable();
+
+        options = options.push(
+            widget::checkbox(option.to_string(), has_option).on_toggle_maybe(searchable.then_some(
+                move |enabled| {
+                    let mut search = search.clone();
+                    search.options.0.insert(option.clone(), enabled);
+                    Message::SavedSearchEdited(search)
+                },
+            )),
+        );
+    }
+
+    widget::column!()
+        .spacing(8)
+        .push(
+            widget::text_input("Nazwa", &search.name)
+                .on_input(edited(|search, name| search.name = name)),
+        )
+        .push(
+            widget::text_input(
+                "Set lub nazwa przedmiotu",
+                search.set.as_deref().unwrap_or(""),
+            )
+            .on_input(edited(|search, set| {
+                search.set = Some(set).filter(|set| !set.is_empty())
+            })),
+        )
+        .push(widget::pick_list(&ItemType::ALL[..], search.item_type, {
+            let search = search.clone();
+            move |item_type| {
+                let mut search = search.clone();
+                search.set_item_type(item_type);
+                Message::SavedSearchEdited(search)
+            }
+        }))
+        .push(options)
+        .push(
+            widget::row!()
+                .spacing(5)
+                .align_y(Alignment::Center)
+                .push(widget::text("Poziom opcji od"))
+                .push(widget::pick_list(
+                    (0..=search.levels.max).collect::<Vec<u8>>(),
+                    Some(search.levels.min),
+                    {
+                        let search = search.clone();
+                        move |min| {
+                            let mut search = search.clone();
+                            search.levels.min = min;
+                            Message::SavedSearchEdited(search)
+                        }
+                    },
+                ))
+                .push(widget::text("do"))
+                .push(widget::pick_list(
+                    (search.levels.min..=MAX_OPTION_LEVEL).collect::<Vec<u8>>(),
+                    Some(search.levels.max),
+                    {
+                        let search = search.clone();
+                        move |max| {
+                            let mut search = search.clone();
+                            search.levels.max = max;
+                            Message::SavedSearchEdited(search)
+                        }
+                    },
+                )),
+        )
+        .push(widget::pick_list(&SortOrder::ALL[..], Some(search.sort), {
+            let search = search.clone();
+            move |sort| {
+                let mut search = search.clone();
+                search.sort = sort;
+                Message::SavedSearchEdited(search)
+            }
+        }))
+        .push(
+            widget::text_input("Waluta (kod)", search.currency.as_deref().unwrap_or("")).on_input(
+                edited(|search, currency| {
+                    search.currency = Some(currency).filter(|currency| !currency.is_empty())
+                }),
+            ),
+        )
+        .push(
+            widget::row!()
+                .spacing(5)
+                .push(widget::button("Zapisz").on_press(Message::ApplySavedSearchEdit))
+                .push(widget::button("Anuluj").on_press(Message::CancelSavedSearchEdit)),
+        )
+        .into()
 }
 
 /// The page to display in the application.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub enum Page {
     DarkWizard,
     DarkKnight,
@@ -496,4 +3345,57 @@ impl Page {
         Page::DarkLord,
         Page::RageFighter,
     ];
+
+    pub fn of(class: &ClassSets) -> Page {
+        match class {
+            ClassSets::DarkWizard(_) => Page::DarkWizard,
+            ClassSets::DarkKnight(_) => Page::DarkKnight,
+            ClassSets::Elf(_) => Page::Elf,
+            ClassSets::Summoner(_) => Page::Summoner,
+            ClassSets::MagicGladiator(_) => Page::MagicGladiator,
+            ClassSets::DarkLord(_) => Page::DarkLord,
+            ClassSets::RageFighter(_) => Page::RageFighter,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = OsString> {
+        args.iter()
+            .map(OsString::from)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn launch_from_args() {
+        assert!(matches!(Launch::from_args(args(&[])), Ok(Launch::Default)));
+        assert!(matches!(
+            Launch::from_args(args(&["a.dreamcol"])),
+            Ok(Launch::View(path)) if path == Path::new("a.dreamcol")
+        ));
+        assert!(matches!(
+            Launch::from_args(args(&["--import", "a.dreamcol", "b.dreamcol"])),
+            Ok(Launch::Import(paths)) if paths.len() == 2
+        ));
+        assert!(matches!(
+            Launch::from_args(args(&["--", "--import"])),
+            Ok(Launch::View(path)) if path == Path::new("--import")
+        ));
+    }
+
+    #[test]
+    fn launch_rejects_bad_args() {
+        assert!(Launch::from_args(args(&["--imprt", "a.dreamcol"])).is_err());
+        assert!(Launch::from_args(args(&["--import"])).is_err());
+        assert!(Launch::from_args(args(&["a.dreamcol", "b.dreamcol"])).is_err());
+    }
+
+    #[test]
+    fn loading_a_bad_collection_fails() {
+        assert!(PlayerCollection::load(Path::new("/nonexistent/collections.ron")).is_err());
+    }
 }
@@ -0,0 +1,274 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::gql;
+use crate::store;
+
+/// How many lots per query and currency are remembered.
+const HISTORY_LIMIT: usize = 200;
+
+/// Latest price seen for a lot.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SeenPrice {
+    pub lot_id: String,
+    pub value: i64,
+}
+
+/// Prices seen in earlier results, per query key and currency title, oldest first.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PriceHistory {
+    pub queries: BTreeMap<String, BTreeMap<String, Vec<SeenPrice>>>,
+}
+
+impl PriceHistory {
+    pub fn path(app_dir: &Path) -> PathBuf {
+        app_dir.join("price_history.ron")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        store::load(path)
+    }
+
+    pub fn save(&self, path: &Path) {
+        store::save(self, path);
+    }
+
+    /// Remembers the current price of each lot, so a lot seen in many
+    /// results still counts once. Lots without an id are skipped.
+    pub fn record(&mut self, key: &str, lots: &[gql::Item]) {
+        let currencies = self.queries.entry(key.to_string()).or_default();
+
+        for lot in lots {
+            let Some(lot_id) = lot.id.as_ref() else {
+                continue;
+            };
+
+            for (currency, value) in lot_prices(lot) {
+                let prices = currencies.entry(currency).or_default();
+                prices.retain(|seen| seen.lot_id != *lot_id);
+                prices.push(SeenPrice {
+                    lot_id: lot_id.clone(),
+                    value,
+                });
+
+                if prices.len() > HISTORY_LIMIT {
+                    prices.drain(..prices.len() - HISTORY_LIMIT);
+                }
+            }
+        }
+    }
+
+    pub fn medians(&self, key: &str) -> BTreeMap<String, f64> {
+        self.queries
+            .get(key)
+            .map(|currencies| {
+                currencies
+                    .iter()
+                    .filter_map(|(currency, prices)| {
+                        let values: Vec<i64> = prices.iter().map(|seen| seen.value).collect();
+                        Some((currency.clone(), median(&values)?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// How good a lot is compared to its peers and to earlier results.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DealScore {
+    /// 0 to 100, 50 means priced at the reference median with every wanted option.
+    pub score: u8,
+    /// Lot price divided by the median of the current results.
+    pub peer_ratio: f64,
+    /// Lot price divided by the median of earlier results, if any were seen.
+    pub history_ratio: Option<f64>,
+    pub covered: usize,
+    pub wanted: usize,
+}
+
+/// Scores every lot, `None` for lots without a usable price.
+///
+/// `covered` tells how many of the `wanted` options the lot at an index carries.
+pub fn score_lots(
+    lots: &[gql::Item],
+    history: &BTreeMap<String, f64>,
+    wanted: usize,
+    covered: impl Fn(usize) -> usize,
+) -> Vec<Option<DealScore>> {
+    let mut peers: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+
+    for (currency, value) in lots.iter().flat_map(lot_prices) {
+        peers.entry(currency).or_default().push(value);
+    }
+
+    let peer_medians: BTreeMap<String, f64> = peers
+        .iter()
+        .filter_map(|(currency, prices)| Some((currency.clone(), median(prices)?)))
+        .collect();
+
+    lots.iter()
+        .enumerate()
+        .map(|(index, lot)| {
+            let mut peer_ratios = vec![];
+            let mut history_ratios = vec![];
+
+            for (currency, value) in lot_prices(lot) {
+                if let Some(peer) = peer_medians.get(&currency).filter(|m| **m > 0.0) {
+                    peer_ratios.push(value as f64 / peer);
+                }
+                if let Some(seen) = history.get(&currency).filter(|m| **m > 0.0) {
+                    history_ratios.push(value as f64 / seen);
+                }
+            }
+
+            let peer_ratio = mean(&peer_ratios)?;
+            let history_ratio = mean(&history_ratios);
+
+            let ratio = match history_ratio {
+                Some(history_ratio) => (peer_ratio + history_ratio) / 2.0,
+                None => peer_ratio,
+            };
+            let covered = covered(index).min(wanted);
+            let coverage = if wanted == 0 {
+                1.0
+            } else {
+                covered as f64 / wanted as f64
+            };
+
+            let price_score = ((1.0 - ratio) * 100.0 + 50.0).clamp(0.0, 100.0);
+            let score = price_score * (0.5 + 0.5 * coverage);
+
+            Some(DealScore {
+                score: score.round() as u8,
+                peer_ratio,
+                history_ratio,
+                covered,
+                wanted,
+            })
+        })
+        .collect()
+}
+
+fn lot_prices(lot: &gql::Item) -> impl Iterator<Item = (String, i64)> + '_ {
+    lot.prices
+        .iter()
+        .filter_map(|price| Some((price.currency.title.clone()?, price.value?)))
+}
+
+fn median(values: &[i64]) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+
+    let mid = sorted.len() / 2;
+
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((sorted[mid - 1] + sorted[mid]) as f64 / 2.0),
+        _ => Some(sorted[mid] as f64),
+    }
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lot(id: &str, prices: &[(&str, i64)]) -> gql::Item {
+        let prices: Vec<_> = prices
+            .iter()
+            .map(|(currency, value)| {
+                serde_json::json!({
+                    "value": value,
+                    "Currency": {"title": currency, "__typename": "Currency"},
+                    "__typename": "Price",
+                })
+            })
+            .collect();
+
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "Prices": prices,
+            "__typename": "Lot",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn median_of_odd_even_and_empty() {
+        assert_eq!(median(&[3, 1, 2]), Some(2.0));
+        assert_eq!(median(&[4, 1, 3, 2]), Some(2.5));
+        assert_eq!(median(&[]), None);
+    }
+
+    #[test]
+    fn mean_of_values() {
+        assert_eq!(mean(&[1.0, 2.0, 6.0]), Some(3.0));
+        assert_eq!(mean(&[]), None);
+    }
+
+    #[test]
+    fn record_keeps_the_latest_price_per_lot() {
+        let mut history = PriceHistory::default();
+
+        history.record("q", &[lot("a", &[("Zen", 100)]), lot("b", &[("Zen", 300)])]);
+        history.record("q", &[lot("a", &[("Zen", 200)])]);
+        history.record("q", &[lot("a", &[("Zen", 200)])]);
+
+        assert_eq!(history.queries["q"]["Zen"].len(), 2);
+        assert_eq!(history.medians("q")["Zen"], 250.0);
+    }
+
+    #[test]
+    fn score_lots_rewards_cheap_and_complete_lots() {
+        let lots = [
+            lot("cheap", &[("Zen", 50)]),
+            lot("median", &[("Zen", 100)]),
+            lot("pricey", &[("Zen", 150)]),
+            lot("free", &[]),
+        ];
+
+        let scores = score_lots(
+            &lots,
+            &BTreeMap::new(),
+            2,
+            |index| {
+                if index == 1 { 1 } else { 2 }
+            },
+        );
+
+        let cheap = scores[0].as_ref().unwrap();
+        assert_eq!(cheap.peer_ratio, 0.5);
+        assert_eq!(cheap.score, 100);
+
+        // At the peer median, missing half of the wanted options.
+        let median = scores[1].as_ref().unwrap();
+        assert_eq!((median.covered, median.wanted), (1, 2));
+        assert_eq!(median.score, 38);
+
+        assert_eq!(scores[2].as_ref().unwrap().score, 0);
+        assert!(scores[3].is_none());
+    }
+
+    #[test]
+    fn score_lots_compares_with_history() {
+        let lots = [lot("a", &[("Zen", 100)])];
+        let history = BTreeMap::from([("Zen".to_string(), 200.0)]);
+
+        let score = score_lots(&lots, &history, 0, |_| 0)[0].clone().unwrap();
+
+        assert_eq!(score.history_ratio, Some(0.5));
+        assert_eq!(score.score, 75);
+    }
+}
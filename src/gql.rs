@@ -1,31 +1,61 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+/// Market lots query. The types below follow the fields it selects, written
+/// by hand as the market schema is not vendored.
+pub const GET_ALL_LOTS: &str = include_str!("../graphql/get_all_lots.graphql");
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Vars {
-    pub filter: Filter,
-    pub limit: u32,
-    pub offset: u32,
-    pub sort: Sort,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<Sort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>,
 }
 
-#[derive(Debug, Serialize)]
+/// Unset fields are left out, the market treats them as no restriction.
+#[derive(Debug, Clone, Serialize)]
 pub struct Filter {
-    pub dd: Option<Vec<u8>>,
-    pub dsr: Option<Vec<u8>>,
-    pub iml: Option<Vec<u8>>,
-    pub imsd: Option<Vec<u8>>,
-    pub izdr: Option<Vec<u8>>,
-    pub rd: Option<Vec<u8>>,
-    #[serde(rename = "type")]
-    pub item_type: Option<Vec<String>>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iml: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imsd: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dd: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rd: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dsr: Option<Vec<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub izdr: Option<Vec<i64>>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Sort {
-    pub field: String,
+    pub field: LotField,
     #[serde(rename = "type")]
-    pub sort_type: String,
+    pub type_: SortType,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum LotField {
+    #[serde(rename = "LOT_FIELD_MIN_PRICE")]
+    MinPrice,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub enum SortType {
+    #[serde(rename = "SORT_TYPE_ASC")]
+    Asc,
+    #[serde(rename = "SORT_TYPE_DESC")]
+    Desc,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -48,38 +78,38 @@ pub struct Item {
     pub source: Option<String>,
     pub is_mine: Option<bool>,
     #[serde(rename = "type")]
-    pub item_type: Option<String>,
-    pub gear_score: Option<u32>,
+    pub type_: Option<String>,
+    pub gear_score: Option<i64>,
     pub has_pending_counter_offer: Option<bool>,
     #[serde(rename = "Prices")]
-    pub prices: Vec<Prices>,
+    pub prices: Vec<Price>,
     #[serde(rename = "Currencies")]
     pub currencies: Option<Vec<Currencies>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct Prices {
-    pub value: Option<u32>,
+pub struct Price {
+    pub value: Option<i64>,
     #[serde(rename = "Currency")]
     pub currency: Currency,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Currency {
-    pub id: Option<u8>,
+    pub id: Option<i64>,
     pub code: Option<String>,
     #[serde(rename = "type")]
-    pub currency_type: Option<String>,
+    pub type_: Option<String>,
     pub title: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Currencies {
-    pub id: Option<u8>,
+    pub id: Option<i64>,
     pub code: Option<String>,
     #[serde(rename = "type")]
-    pub currencies_type: Option<String>,
+    pub type_: Option<String>,
     pub title: Option<String>,
     pub is_available_for_lots: Option<bool>,
 }
@@ -87,7 +117,7 @@ pub struct Currencies {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Pagination {
-    pub total: u32,
-    pub current_page: u32,
+    pub total: i64,
+    pub current_page: i64,
     pub next_page_exists: bool,
 }
@@ -6,9 +6,10 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
-use crate::gql::Vars;
+use crate::gql::Filter;
+use crate::search::{LevelRange, SavedSearch, SortOrder};
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub enum ItemType {
     #[default]
     Helm,
@@ -16,6 +17,23 @@ pub enum ItemType {
     Pants,
     Gloves,
     Boots,
+    // Weapons
+    Sword,
+    Axe,
+    Mace,
+    Spear,
+    Bow,
+    Crossbow,
+    Staff,
+    Stick,
+    Book,
+    Scepter,
+    Claw,
+    // Other equipment
+    Shield,
+    Wings,
+    Pendant,
+    Ring,
 }
 
 impl From<String> for ItemType {
@@ -26,6 +44,21 @@ impl From<String> for ItemType {
             "Pants" => ItemType::Pants,
             "Gloves" => ItemType::Gloves,
             "Boots" => ItemType::Boots,
+            "Sword" => ItemType::Sword,
+            "Axe" => ItemType::Axe,
+            "Mace" => ItemType::Mace,
+            "Spear" => ItemType::Spear,
+            "Bow" => ItemType::Bow,
+            "Crossbow" => ItemType::Crossbow,
+            "Staff" => ItemType::Staff,
+            "Stick" => ItemType::Stick,
+            "Book" => ItemType::Book,
+            "Scepter" => ItemType::Scepter,
+            "Claw" => ItemType::Claw,
+            "Shield" => ItemType::Shield,
+            "Wings" => ItemType::Wings,
+            "Pendant" => ItemType::Pendant,
+            "Ring" => ItemType::Ring,
 
             _ => panic!("Unknown item type: {}", item_str),
         }
@@ -40,177 +73,486 @@ impl Display for ItemType {
             ItemType::Pants => "Pants",
             ItemType::Gloves => "Gloves",
             ItemType::Boots => "Boots",
+            ItemType::Sword => "Sword",
+            ItemType::Axe => "Axe",
+            ItemType::Mace => "Mace",
+            ItemType::Spear => "Spear",
+            ItemType::Bow => "Bow",
+            ItemType::Crossbow => "Crossbow",
+            ItemType::Staff => "Staff",
+            ItemType::Stick => "Stick",
+            ItemType::Book => "Book",
+            ItemType::Scepter => "Scepter",
+            ItemType::Claw => "Claw",
+            ItemType::Shield => "Shield",
+            ItemType::Wings => "Wings",
+            ItemType::Pendant => "Pendant",
+            ItemType::Ring => "Ring",
         };
         write!(f, "{}", item_str)
     }
 }
 
+impl ItemType {
+    pub const ALL: [ItemType; 20] = [
+        ItemType::Helm,
+        ItemType::Armor,
+        ItemType::Pants,
+        ItemType::Gloves,
+        ItemType::Boots,
+        ItemType::Sword,
+        ItemType::Axe,
+        ItemType::Mace,
+        ItemType::Spear,
+        ItemType::Bow,
+        ItemType::Crossbow,
+        ItemType::Staff,
+        ItemType::Stick,
+        ItemType::Book,
+        ItemType::Scepter,
+        ItemType::Claw,
+        ItemType::Shield,
+        ItemType::Wings,
+        ItemType::Pendant,
+        ItemType::Ring,
+    ];
+
+    pub fn category(&self) -> ItemCategory {
+        match self {
+            ItemType::Helm
+            | ItemType::Armor
+            | ItemType::Pants
+            | ItemType::Gloves
+            | ItemType::Boots => ItemCategory::Armor,
+            ItemType::Sword
+            | ItemType::Axe
+            | ItemType::Mace
+            | ItemType::Spear
+            | ItemType::Bow
+            | ItemType::Crossbow
+            | ItemType::Staff
+            | ItemType::Stick
+            | ItemType::Book
+            | ItemType::Scepter
+            | ItemType::Claw => ItemCategory::Weapon,
+            ItemType::Shield => ItemCategory::Shield,
+            ItemType::Wings => ItemCategory::Wings,
+            ItemType::Pendant => ItemCategory::Pendant,
+            ItemType::Ring => ItemCategory::Ring,
+        }
+    }
+
+    /// Value of the `type` field in the market filter. Only the armor values
+    /// are confirmed by the market, other gear is searched by name alone.
+    pub fn gql_type(&self) -> Option<&'static str> {
+        match self {
+            ItemType::Helm => Some("helm"),
+            ItemType::Armor => Some("armor"),
+            ItemType::Pants => Some("pants"),
+            ItemType::Gloves => Some("gloves"),
+            ItemType::Boots => Some("boots"),
+            _ => None,
+        }
+    }
+}
+
+/// Broad equipment category, each with its own option vocabulary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemCategory {
+    Armor,
+    Weapon,
+    Shield,
+    Wings,
+    Pendant,
+    Ring,
+}
+
+impl Display for ItemCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let category_str = match self {
+            ItemCategory::Armor => "Armor",
+            ItemCategory::Weapon => "Weapons",
+            ItemCategory::Shield => "Shields",
+            ItemCategory::Wings => "Wings",
+            ItemCategory::Pendant => "Pendants",
+            ItemCategory::Ring => "Rings",
+        };
+        write!(f, "{}", category_str)
+    }
+}
+
+impl ItemCategory {
+    /// Categories tracked outside of the armor sets.
+    pub const GEAR: [ItemCategory; 5] = [
+        ItemCategory::Weapon,
+        ItemCategory::Shield,
+        ItemCategory::Wings,
+        ItemCategory::Pendant,
+        ItemCategory::Ring,
+    ];
+
+    pub fn options(&self) -> Vec<ItemOptionType> {
+        ItemOptionType::ALL
+            .into_iter()
+            .filter(|option| option.spec().categories.contains(self))
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ItemOptionType {
-    MH = 0,
-    SD = 1,
-    DD = 2,
-    Ref = 3,
-    Dsr = 4,
-    Zen = 5,
+    MH,
+    SD,
+    DD,
+    Ref,
+    Dsr,
+    Zen,
+    // Weapon and pendant options
+    ExcDmg,
+    DmgLevel,
+    DmgPercent,
+    Speed,
+    LifeHunt,
+    ManaHunt,
+    // Wing options
+    IgnoreDef,
+    ReturnDmg,
+    RecoverLife,
+    RecoverMana,
+}
+
+impl ItemOptionType {
+    pub const ALL: [ItemOptionType; 16] = [
+        ItemOptionType::MH,
+        ItemOptionType::SD,
+        ItemOptionType::DD,
+        ItemOptionType::Ref,
+        ItemOptionType::Dsr,
+        ItemOptionType::Zen,
+        ItemOptionType::ExcDmg,
+        ItemOptionType::DmgLevel,
+        ItemOptionType::DmgPercent,
+        ItemOptionType::Speed,
+        ItemOptionType::LifeHunt,
+        ItemOptionType::ManaHunt,
+        ItemOptionType::IgnoreDef,
+        ItemOptionType::ReturnDmg,
+        ItemOptionType::RecoverLife,
+        ItemOptionType::RecoverMana,
+    ];
+
+    pub fn spec(&self) -> &'static OptionSpec {
+        OPTION_SCHEMA
+            .iter()
+            .find(|spec| spec.option == *self)
+            .unwrap_or_else(|| panic!("Missing option schema entry: {:?}", self))
+    }
+
+    /// Whether the market can filter lots by this option.
+    pub fn is_searchable(&self) -> bool {
+        self.spec().filter.is_some()
+    }
 }
 
 impl Display for ItemOptionType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let option_str = match self {
-            ItemOptionType::MH => "Maximum Life (MH)",
-            ItemOptionType::SD => "Increase Maximum SD (SD)",
-            ItemOptionType::DD => "Damage Decrease (DD)",
-            ItemOptionType::Ref => "Damage Reflection (REF)",
-            ItemOptionType::Dsr => "Defense Success Rate (DSR)",
-            ItemOptionType::Zen => "Additional Zen drop rate (ZEN)",
-        };
-        write!(f, "{}", option_str)
+        write!(f, "{}", self.spec().label)
     }
 }
 
+/// Market filter field holding the accepted levels of an option.
+pub type FilterField = fn(&mut Filter) -> &mut Option<Vec<i64>>;
+
+/// Describes an item option: how it is labeled, which categories roll it
+/// and which market filter field searches for it.
+#[derive(Debug)]
+pub struct OptionSpec {
+    pub option: ItemOptionType,
+    pub label: &'static str,
+    /// `None` while the market has no confirmed field for the option.
+    pub filter: Option<FilterField>,
+    pub levels: &'static [u8],
+    pub categories: &'static [ItemCategory],
+}
+
+const DEFENSIVE: &[ItemCategory] = &[
+    ItemCategory::Armor,
+    ItemCategory::Shield,
+    ItemCategory::Ring,
+];
+const OFFENSIVE: &[ItemCategory] = &[ItemCategory::Weapon, ItemCategory::Pendant];
+const WINGS: &[ItemCategory] = &[ItemCategory::Wings];
+
+pub const MAX_OPTION_LEVEL: u8 = 4;
+const ALL_LEVELS: &[u8] = &[0, 1, 2, 3, MAX_OPTION_LEVEL];
+
+/// Every known option. Adding an option means adding a variant to
+/// `ItemOptionType`, listing it in `ItemOptionType::ALL` and a row here.
+pub const OPTION_SCHEMA: &[OptionSpec] = &[
+    OptionSpec {
+        option: ItemOptionType::MH,
+        label: "Maximum Life (MH)",
+        filter: Some(|filter| &mut filter.iml),
+        levels: ALL_LEVELS,
+        categories: DEFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::SD,
+        label: "Increase Maximum SD (SD)",
+        filter: Some(|filter| &mut filter.imsd),
+        levels: ALL_LEVELS,
+        categories: DEFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::DD,
+        label: "Damage Decrease (DD)",
+        filter: Some(|filter| &mut filter.dd),
+        levels: ALL_LEVELS,
+        categories: DEFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::Ref,
+        label: "Damage Reflection (REF)",
+        filter: Some(|filter| &mut filter.rd),
+        levels: ALL_LEVELS,
+        categories: DEFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::Dsr,
+        label: "Defense Success Rate (DSR)",
+        filter: Some(|filter| &mut filter.dsr),
+        levels: ALL_LEVELS,
+        categories: DEFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::Zen,
+        label: "Additional Zen drop rate (ZEN)",
+        filter: Some(|filter| &mut filter.izdr),
+        levels: ALL_LEVELS,
+        categories: DEFENSIVE,
+    },
+    // The weapon and wing filter fields are not confirmed by the market yet,
+    // and an unknown field makes it reject the whole query.
+    OptionSpec {
+        option: ItemOptionType::ExcDmg,
+        label: "Excellent Damage Rate (EXC)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: OFFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::DmgLevel,
+        label: "Increase Damage +level/20 (LVL)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: OFFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::DmgPercent,
+        label: "Increase Damage +2% (DMG)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: OFFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::Speed,
+        label: "Increase Attack Speed (SPD)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: OFFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::LifeHunt,
+        label: "Life after hunt (LAH)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: OFFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::ManaHunt,
+        label: "Mana after hunt (MAH)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: OFFENSIVE,
+    },
+    OptionSpec {
+        option: ItemOptionType::IgnoreDef,
+        label: "Ignore Defense (IGN)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: WINGS,
+    },
+    OptionSpec {
+        option: ItemOptionType::ReturnDmg,
+        label: "Return Damage (RET)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: WINGS,
+    },
+    OptionSpec {
+        option: ItemOptionType::RecoverLife,
+        label: "Full Life Recovery (RLR)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: WINGS,
+    },
+    OptionSpec {
+        option: ItemOptionType::RecoverMana,
+        label: "Full Mana Recovery (RMR)",
+        filter: None,
+        levels: ALL_LEVELS,
+        categories: WINGS,
+    },
+];
+
 pub type ItemOption = ItemOptionType;
 pub type ItemHasOption = bool;
 
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct ItemOptions(pub BTreeMap<ItemOption, ItemHasOption>);
 
+/// Armor options, as collections saved before other gear hold only armor.
 impl Default for ItemOptions {
     fn default() -> Self {
-        let mut options = BTreeMap::new();
+        ItemOptions::for_category(ItemCategory::Armor)
+    }
+}
+
+impl ItemOptions {
+    pub fn for_type(item_type: ItemType) -> Self {
+        ItemOptions::for_category(item_type.category())
+    }
+
+    fn for_category(category: ItemCategory) -> Self {
+        ItemOptions(
+            category
+                .options()
+                .into_iter()
+                .map(|option| (option, false))
+                .collect(),
+        )
+    }
+}
 
-        options.insert(ItemOptionType::MH, false);
-        options.insert(ItemOptionType::SD, false);
-        options.insert(ItemOptionType::DD, false);
-        options.insert(ItemOptionType::Ref, false);
-        options.insert(ItemOptionType::Dsr, false);
-        options.insert(ItemOptionType::Zen, false);
+/// Which options a market search for an item asks for.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Exactly the options not owned yet.
+    #[default]
+    Missing,
+    /// The options picked in `Item::wanted`.
+    Custom,
+}
 
-        ItemOptions(options)
+impl Display for SearchMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mode_str = match self {
+            SearchMode::Missing => "Brakujące",
+            SearchMode::Custom => "Własne",
+        };
+        write!(f, "{}", mode_str)
     }
 }
 
+impl SearchMode {
+    pub const ALL: [SearchMode; 2] = [SearchMode::Missing, SearchMode::Custom];
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Item {
+    /// Options the player has collected.
     pub options: Arc<Mutex<ItemOptions>>,
     pub item_type: Option<ItemType>,
     pub name: Option<String>,
+    /// Options to search for in `SearchMode::Custom`.
+    #[serde(default)]
+    pub wanted: ItemOptions,
+    #[serde(default)]
+    pub search_mode: SearchMode,
 }
 
 impl Item {
     pub fn new(name: String, item_type: ItemType) -> Self {
         Item {
-            options: Arc::new(Mutex::new(ItemOptions::default())),
+            options: Arc::new(Mutex::new(ItemOptions::for_type(item_type))),
             item_type: Some(item_type),
             name: Some(name),
+            wanted: ItemOptions::for_type(item_type),
+            search_mode: SearchMode::default(),
         }
     }
 }
 
 impl Item {
-    pub fn generate_market_query(&self) -> String {
-        r#"
-            query GET_ALL_LOTS($offset: NonNegativeInt, $limit: NonNegativeInt, $sort: LotsSortInput, $filter: LotsFilterInput) {
-              lots(limit: $limit, offset: $offset, sort: $sort, filter: $filter) {
-                Lots {
-                  id
-                  source
-                  isMine
-                  type
-                  gearScore
-                  hasPendingCounterOffer
-                  Prices {
-                    value
-                    Currency {
-                      id
-                      code
-                      type
-                      title
-                      __typename
-                    }
-                    __typename
-                  }
-                  Currencies {
-                    id
-                    code
-                    type
-                    title
-                    isAvailableForLots
-                    __typename
-                  }
-                  __typename
-                }
-                Pagination {
-                  total
-                  currentPage
-                  nextPageExists
-                  __typename
-                }
-                __typename
-              }
+    /// Display title, e.g. "Pad Helm" for armor or "Legendary Staff" for gear.
+    pub fn title(&self) -> String {
+        let name = self.name.clone().unwrap_or_default();
+
+        match self.item_type {
+            Some(item_type) if item_type.category() == ItemCategory::Armor => {
+                format!("{} {}", name, item_type)
             }
-        "#.to_string()
-    }
-
-    pub fn generate_gql_vars(&self) -> Vars {
-        let options = self.options.lock().unwrap();
-
-        Vars {
-            filter: crate::gql::Filter {
-                dd: options.0.get(&ItemOptionType::DD).and_then(|has_option| {
-                    if *has_option {
-                        Some(vec![0, 1, 2, 3, 4])
-                    } else {
-                        None
-                    }
-                }),
-                dsr: options.0.get(&ItemOptionType::Dsr).and_then(|has_option| {
-                    if *has_option {
-                        Some(vec![0, 1, 2, 3, 4])
-                    } else {
-                        None
-                    }
-                }),
-                iml: options.0.get(&ItemOptionType::MH).and_then(|has_option| {
-                    if *has_option {
-                        Some(vec![0, 1, 2, 3, 4])
-                    } else {
-                        None
-                    }
-                }),
-                imsd: options.0.get(&ItemOptionType::SD).and_then(|has_option| {
-                    if *has_option {
-                        Some(vec![0, 1, 2, 3, 4])
-                    } else {
-                        None
-                    }
-                }),
-                rd: options.0.get(&ItemOptionType::Ref).and_then(|has_option| {
-                    if *has_option {
-                        Some(vec![0, 1, 2, 3, 4])
-                    } else {
-                        None
-                    }
-                }),
-                izdr: options.0.get(&ItemOptionType::Zen).and_then(|has_option| {
-                    if *has_option {
-                        Some(vec![0, 1, 2, 3, 4])
-                    } else {
-                        None
-                    }
-                }),
-
-                item_type: Some(vec![
-                    self.item_type.as_ref().unwrap().to_string().to_lowercase(),
-                ]),
-                name: self.name.clone(),
-            },
-            limit: 200,
-            offset: 0,
-            sort: crate::gql::Sort {
-                field: "LOT_FIELD_MIN_PRICE".to_string(),
-                sort_type: "SORT_TYPE_ASC".to_string(),
-            },
+            _ => name,
+        }
+    }
+
+    /// Whether `name` is this item's set or item name, or its full title,
+    /// ignoring case and spacing. "Dragon" is not "Black Dragon".
+    pub fn is_named(&self, name: &str) -> bool {
+        let words = |text: &str| {
+            text.split_whitespace()
+                .map(str::to_lowercase)
+                .collect::<Vec<String>>()
+        };
+        let name = words(name);
+
+        !name.is_empty()
+            && (self.name.as_deref().is_some_and(|own| words(own) == name)
+                || words(&self.title()) == name)
+    }
+
+    /// Options a market search for this item looks for, or `None` when
+    /// the missing mode has nothing left to find.
+    pub fn search_options(&self) -> Option<ItemOptions> {
+        let owned = self.options.lock().unwrap();
+
+        if self.search_mode == SearchMode::Missing && owned.0.values().all(|has_option| *has_option)
+        {
+            return None;
+        }
+
+        Some(ItemOptions(
+            owned
+                .0
+                .iter()
+                .map(|(option, has_option)| {
+                    let wanted = match self.search_mode {
+                        SearchMode::Missing => !has_option,
+                        SearchMode::Custom => self.wanted.0.get(option).copied().unwrap_or(false),
+                    };
+                    (option.clone(), wanted && option.is_searchable())
+                })
+                .collect(),
+        ))
+    }
+
+    /// Search definition built from the item's wanted options, `None` when
+    /// there is nothing to search for.
+    pub fn to_search(&self, sort: SortOrder) -> Option<SavedSearch> {
+        self.search_options()
+            .map(|options| self.search_with(options, sort))
+    }
+
+    /// Search definition for this item requiring `options`.
+    pub fn search_with(&self, options: ItemOptions, sort: SortOrder) -> SavedSearch {
+        SavedSearch {
+            name: self.title(),
+            set: self.name.clone(),
+            item_type: self.item_type,
+            options,
+            levels: LevelRange::default(),
+            sort,
+            currency: None,
         }
     }
 }
@@ -277,6 +619,122 @@ pub enum ClassSets {
     RageFighter(Vec<SetItems>),
 }
 
+impl ClassSets {
+    pub fn sets(&self) -> &Vec<SetItems> {
+        match self {
+            ClassSets::DarkWizard(sets) => sets,
+            ClassSets::DarkKnight(sets) => sets,
+            ClassSets::Elf(sets) => sets,
+            ClassSets::MagicGladiator(sets) => sets,
+            ClassSets::DarkLord(sets) => sets,
+            ClassSets::Summoner(sets) => sets,
+            ClassSets::RageFighter(sets) => sets,
+        }
+    }
+
+    /// Display name of the class, e.g. "Dark Knight".
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            ClassSets::DarkWizard(_) => "Dark Wizard",
+            ClassSets::DarkKnight(_) => "Dark Knight",
+            ClassSets::Elf(_) => "Elf",
+            ClassSets::MagicGladiator(_) => "Magic Gladiator",
+            ClassSets::DarkLord(_) => "Dark Lord",
+            ClassSets::Summoner(_) => "Summoner",
+            ClassSets::RageFighter(_) => "Rage Fighter",
+        }
+    }
+
+    /// Weapon types the class can equip.
+    pub fn weapon_types(&self) -> &'static [ItemType] {
+        match self {
+            ClassSets::DarkWizard(_) => &[ItemType::Staff],
+            ClassSets::DarkKnight(_) => &[
+                ItemType::Sword,
+                ItemType::Axe,
+                ItemType::Mace,
+                ItemType::Spear,
+            ],
+            ClassSets::Elf(_) => &[ItemType::Bow, ItemType::Crossbow],
+            ClassSets::MagicGladiator(_) => &[ItemType::Sword, ItemType::Staff],
+            ClassSets::DarkLord(_) => &[ItemType::Scepter],
+            ClassSets::Summoner(_) => &[ItemType::Stick, ItemType::Book],
+            ClassSets::RageFighter(_) => &[ItemType::Claw],
+        }
+    }
+}
+
+/// Known non-armor items tracked in the collection.
+const GEAR_CATALOGUE: &[(ItemType, &str)] = &[
+    // Weapons
+    (ItemType::Sword, "Legendary Sword"),
+    (ItemType::Sword, "Sword of Salamander"),
+    (ItemType::Sword, "Light Saber"),
+    (ItemType::Sword, "Thunder Blade"),
+    (ItemType::Sword, "Dark Breaker"),
+    (ItemType::Axe, "Crescent Axe"),
+    (ItemType::Axe, "Elven Axe"),
+    (ItemType::Mace, "Great Hammer"),
+    (ItemType::Mace, "Lord Scepter"),
+    (ItemType::Spear, "Dragon Lance"),
+    (ItemType::Spear, "Giant Trident"),
+    (ItemType::Bow, "Silver Bow"),
+    (ItemType::Bow, "Chaos Nature Bow"),
+    (ItemType::Bow, "Celestial Bow"),
+    (ItemType::Crossbow, "Arquebus"),
+    (ItemType::Crossbow, "Saint Crossbow"),
+    (ItemType::Staff, "Legendary Staff"),
+    (ItemType::Staff, "Staff of Resurrection"),
+    (ItemType::Staff, "Chaos Lightning Staff"),
+    (ItemType::Staff, "Staff of Destruction"),
+    (ItemType::Stick, "Demonic Stick"),
+    (ItemType::Stick, "Storm Blitz Stick"),
+    (ItemType::Book, "Book of Samut"),
+    (ItemType::Book, "Book of Neil"),
+    (ItemType::Book, "Book of Lagle"),
+    (ItemType::Scepter, "Great Scepter"),
+    (ItemType::Scepter, "Shining Scepter"),
+    (ItemType::Claw, "Phoenix Soul Claw"),
+    (ItemType::Claw, "Piercing Blade Glove"),
+    // Shields
+    (ItemType::Shield, "Legendary Shield"),
+    (ItemType::Shield, "Grand Soul Shield"),
+    (ItemType::Shield, "Elemental Shield"),
+    (ItemType::Shield, "Dragon Shield"),
+    (ItemType::Shield, "Serpent Shield"),
+    (ItemType::Shield, "Crimson Glory"),
+    (ItemType::Shield, "Frost Barrier"),
+    // Wings
+    (ItemType::Wings, "Wings of Spirits"),
+    (ItemType::Wings, "Wings of Soul"),
+    (ItemType::Wings, "Wings of Dragon"),
+    (ItemType::Wings, "Wings of Darkness"),
+    (ItemType::Wings, "Cape of Lord"),
+    (ItemType::Wings, "Wings of Despair"),
+    (ItemType::Wings, "Cape of Fighter"),
+    // Pendants
+    (ItemType::Pendant, "Pendant of Lighting"),
+    (ItemType::Pendant, "Pendant of Fire"),
+    (ItemType::Pendant, "Pendant of Ice"),
+    (ItemType::Pendant, "Pendant of Wind"),
+    (ItemType::Pendant, "Pendant of Water"),
+    (ItemType::Pendant, "Pendant of Ability"),
+    // Rings
+    (ItemType::Ring, "Ring of Ice"),
+    (ItemType::Ring, "Ring of Poison"),
+    (ItemType::Ring, "Ring of Fire"),
+    (ItemType::Ring, "Ring of Earth"),
+    (ItemType::Ring, "Ring of Wind"),
+    (ItemType::Ring, "Ring of Magic"),
+];
+
+pub fn default_gear() -> Vec<Arc<Mutex<Item>>> {
+    GEAR_CATALOGUE
+        .iter()
+        .map(|(item_type, name)| Arc::new(Mutex::new(Item::new(name.to_string(), *item_type))))
+        .collect()
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum AllSets {
     // Dark Wizard Sets
@@ -442,3 +900,76 @@ impl From<String> for AllSets {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_option_has_a_schema_row() {
+        for option in ItemOptionType::ALL {
+            assert_eq!(option.spec().option, option);
+        }
+        assert_eq!(OPTION_SCHEMA.len(), ItemOptionType::ALL.len());
+    }
+
+    #[test]
+    fn default_options_come_from_the_armor_schema_rows() {
+        let options: Vec<ItemOptionType> = ItemOptions::default().0.into_keys().collect();
+
+        assert_eq!(options, ItemCategory::Armor.options());
+    }
+
+    #[test]
+    fn missing_mode_with_everything_owned_has_nothing_to_search() {
+        let item = Item::new("Dragon".to_string(), ItemType::Helm);
+        item.options
+            .lock()
+            .unwrap()
+            .0
+            .values_mut()
+            .for_each(|has_option| *has_option = true);
+
+        assert!(item.search_options().is_none());
+        assert!(item.to_search(SortOrder::default()).is_none());
+    }
+
+    #[test]
+    fn missing_mode_searches_for_unowned_options() {
+        let item = Item::new("Dragon".to_string(), ItemType::Helm);
+        item.options
+            .lock()
+            .unwrap()
+            .0
+            .insert(ItemOptionType::DD, true);
+
+        let options = item.search_options().unwrap();
+        assert_eq!(options.0.get(&ItemOptionType::DD), Some(&false));
+        assert_eq!(options.0.get(&ItemOptionType::MH), Some(&true));
+    }
+
+    #[test]
+    fn names_match_whole_set_names() {
+        let pairs = [
+            ("Dragon", "Black Dragon"),
+            ("Dragon", "Great Dragon"),
+            ("Plate", "Light Plate"),
+            ("Spirit", "Holy Spirit"),
+        ];
+
+        for (short, long) in pairs {
+            let short_item = Item::new(short.to_string(), ItemType::Helm);
+            let long_item = Item::new(long.to_string(), ItemType::Helm);
+
+            assert!(short_item.is_named(short));
+            assert!(!short_item.is_named(long), "{short} matched {long}");
+            assert!(long_item.is_named(long));
+            assert!(!long_item.is_named(short), "{long} matched {short}");
+        }
+
+        let helm = Item::new("Black Dragon".to_string(), ItemType::Helm);
+        assert!(helm.is_named("black  dragon helm"));
+        assert!(!helm.is_named("Black Dragon Armor"));
+        assert!(!helm.is_named(""));
+    }
+}
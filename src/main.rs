@@ -1,13 +1,44 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::app::AppModel;
+use crate::app::{AppModel, Launch, USAGE};
+use crate::ui_state::UiState;
 
 mod app;
+mod deals;
 mod gql;
 mod items;
+mod market;
+mod notify;
+mod planner;
+mod purchases;
+mod search;
+mod session;
+mod settings;
+mod shortcuts;
+mod store;
+mod ui_state;
 
 fn main() -> iced::Result {
+    let launch = match Launch::from_args(std::env::args_os().skip(1)) {
+        Ok(launch) => launch,
+        Err(err) => {
+            eprintln!("{err}\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let ui_state = UiState::load(&UiState::path(&app::app_dir())).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        UiState::default()
+    });
+
+    let position = ui_state.window_position();
+
     iced::application(AppModel::title, AppModel::update, AppModel::view)
-        .centered()
-        .run()
+        .subscription(AppModel::subscription)
+        .theme(AppModel::theme)
+        .window_size((ui_state.width, ui_state.height))
+        .position(position)
+        // The window state is saved before closing.
+        .exit_on_close_request(false)
+        .run_with(move || (AppModel::new(launch, ui_state), iced::Task::none()))
 }
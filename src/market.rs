@@ -0,0 +1,518 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use ron::ser::{PrettyConfig, to_string_pretty};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::Instant;
+
+use crate::gql::{Data, Vars};
+use gql_client::Client;
+
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 2;
+/// More parallel requests only get the player rate limited by the market.
+pub const MAX_CONCURRENT_REQUESTS: usize = 16;
+pub const DEFAULT_MAX_REQUESTS_PER_MINUTE: u32 = 30;
+
+/// Outcome of a market search, fresh or answered from the cache.
+#[derive(Debug, Clone)]
+pub struct MarketResult {
+    /// Id from `RequestQueue::next_id`, used to drop superseded results.
+    pub request_id: u64,
+    pub title: String,
+    pub key: String,
+    pub data: Option<Data>,
+    /// When the data was fetched, if it came from the cache.
+    pub cached_at: Option<SystemTime>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CachedLots {
+    pub key: String,
+    pub fetched_at: SystemTime,
+    pub data: Data,
+}
+
+/// In-memory and on-disk cache of market responses keyed by the query variables
+/// and by who asked which server.
+#[derive(Debug)]
+pub struct MarketCache {
+    dir: PathBuf,
+    pub ttl: Duration,
+    entries: HashMap<String, CachedLots>,
+}
+
+impl MarketCache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        MarketCache {
+            dir,
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Key of `vars` sent to `endpoint` in `language`, logged in with `token`.
+    /// Answers differ per server, language and player, e.g. in `isMine`. Only
+    /// a hash of the token is kept, since the key is stored on disk.
+    pub fn key(endpoint: &str, language: &str, token: Option<&str>, vars: &Vars) -> String {
+        let player = token.map(key_hash).unwrap_or_default();
+
+        format!("{endpoint} {language} {player} {}", vars_key(vars))
+    }
+
+    /// Cached response for `key` that is younger than the TTL.
+    pub fn get(&mut self, key: &str) -> Option<CachedLots> {
+        if !self.entries.contains_key(key) {
+            let cached = std::fs::read_to_string(self.file(key))
+                .ok()
+                .and_then(|data| ron::from_str::<CachedLots>(&data).ok())
+                .filter(|cached| cached.key == key)?;
+
+            self.entries.insert(key.to_string(), cached);
+        }
+
+        let cached = self.entries.get(key)?;
+        let age = cached.fetched_at.elapsed().unwrap_or(Duration::MAX);
+
+        if age <= self.ttl {
+            Some(cached.clone())
+        } else {
+            self.entries.remove(key);
+            None
+        }
+    }
+
+    pub fn insert(&mut self, key: String, data: Data) {
+        let cached = CachedLots {
+            key: key.clone(),
+            fetched_at: SystemTime::now(),
+            data,
+        };
+
+        let result = std::fs::create_dir_all(&self.dir).and_then(|_| {
+            let data =
+                to_string_pretty(&cached, PrettyConfig::new()).map_err(std::io::Error::other)?;
+            std::fs::write(self.file(&key), data)
+        });
+
+        if let Err(err) = result {
+            eprintln!("failed to cache market response: {err}");
+        }
+
+        self.entries.insert(key, cached);
+    }
+
+    /// Forgets responses older than the TTL and deletes their files.
+    pub fn prune(&mut self) {
+        let ttl = self.ttl;
+        let expired = |modified: SystemTime| modified.elapsed().unwrap_or_default() > ttl;
+
+        self.entries.retain(|_, cached| !expired(cached.fetched_at));
+
+        let Ok(files) = std::fs::read_dir(&self.dir) else {
+            return;
+        };
+
+        for file in files.flatten() {
+            let path = file.path();
+            let is_expired = file
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(expired);
+
+            if path.extension().is_some_and(|ext| ext == "ron")
+                && is_expired
+                && let Err(err) = std::fs::remove_file(&path)
+            {
+                eprintln!("failed to remove {}: {err}", path.display());
+            }
+        }
+    }
+
+    fn file(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.ron", key_hash(key)))
+    }
+}
+
+/// Query variables as text, the same for every server and player.
+fn vars_key(vars: &Vars) -> String {
+    ron::to_string(vars).unwrap_or_default()
+}
+
+/// File name stem for `key` that stays the same across builds and platforms.
+fn key_hash(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Where market responses come from.
+#[derive(Debug, Clone, Default)]
+pub enum Traffic {
+    #[default]
+    Live,
+    /// Query the API and save every exchange as JSON in the directory.
+    Record(PathBuf),
+    /// Answer queries from recordings in the directory without any network.
+    Replay(PathBuf),
+}
+
+/// One recorded exchange with the market API.
+#[derive(Debug, Deserialize, Serialize)]
+struct Recording {
+    vars: serde_json::Value,
+    data: Option<Data>,
+}
+
+impl Traffic {
+    /// Reads `DREAM_COLLECTIONS_RECORD` or `DREAM_COLLECTIONS_REPLAY`.
+    pub fn from_env() -> Self {
+        if let Some(dir) = std::env::var_os("DREAM_COLLECTIONS_REPLAY") {
+            Traffic::Replay(dir.into())
+        } else if let Some(dir) = std::env::var_os("DREAM_COLLECTIONS_RECORD") {
+            Traffic::Record(dir.into())
+        } else {
+            Traffic::Live
+        }
+    }
+
+    pub fn is_live(&self) -> bool {
+        matches!(self, Traffic::Live)
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Traffic::Replay(_))
+    }
+
+    pub async fn query(self, client: Client, query: &'static str, vars: Vars) -> Option<Data> {
+        let key = vars_key(&vars);
+        let recording_vars = serde_json::to_value(&vars).unwrap_or_default();
+
+        match self {
+            Traffic::Live => live_query(client, query, vars).await,
+            Traffic::Record(dir) => {
+                let data = live_query(client, query, vars).await;
+
+                let recording = Recording {
+                    vars: recording_vars,
+                    data,
+                };
+                let result = std::fs::create_dir_all(&dir).and_then(|_| {
+                    let json = serde_json::to_string_pretty(&recording)?;
+                    std::fs::write(dir.join(format!("{}.json", key_hash(&key))), json)
+                });
+
+                if let Err(err) = result {
+                    eprintln!("failed to record market response: {err}");
+                }
+
+                recording.data
+            }
+            Traffic::Replay(dir) => {
+                let path = dir.join(format!("{}.json", key_hash(&key)));
+
+                match std::fs::read_to_string(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|json| {
+                        serde_json::from_str::<Recording>(&json).map_err(|err| err.to_string())
+                    }) {
+                    Ok(recording) if recording.vars == recording_vars => recording.data,
+                    Ok(_) => {
+                        eprintln!("recording at {} is for other variables", path.display());
+                        None
+                    }
+                    Err(err) => {
+                        eprintln!("no recording at {}: {err}", path.display());
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn live_query(client: Client, query: &'static str, vars: Vars) -> Option<Data> {
+    client
+        .query_with_vars::<Data, Vars>(query, vars)
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("market search failed: {err}");
+            None
+        })
+}
+
+/// Human readable age of a cached response, e.g. "3 min temu".
+pub fn format_age(since: SystemTime) -> String {
+    let age = since.elapsed().unwrap_or_default().as_secs();
+
+    if age < 60 {
+        "przed chwilą".to_string()
+    } else {
+        format!("{} min temu", age / 60)
+    }
+}
+
+/// Limits how many market requests run at once and how many start within
+/// a minute. Requests under the limit start right away, so the queries of one
+/// search are not spread over the whole minute.
+#[derive(Debug)]
+pub struct RequestQueue {
+    permits: Arc<Semaphore>,
+    max_concurrent: usize,
+    max_per_minute: usize,
+    /// Start times of the latest requests, at most `max_per_minute` of them.
+    starts: Arc<Mutex<VecDeque<Instant>>>,
+    next_id: u64,
+}
+
+impl RequestQueue {
+    pub fn new(max_concurrent: usize, max_per_minute: u32) -> Self {
+        let max_concurrent = max_concurrent.clamp(1, MAX_CONCURRENT_REQUESTS);
+
+        RequestQueue {
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent,
+            max_per_minute: max_per_minute.max(1) as usize,
+            starts: Arc::new(Mutex::new(VecDeque::new())),
+            next_id: 0,
+        }
+    }
+
+    /// Applies new limits to requests queued from now on.
+    pub fn set_limits(&mut self, max_concurrent: usize, max_per_minute: u32) {
+        let max_concurrent = max_concurrent.clamp(1, MAX_CONCURRENT_REQUESTS);
+
+        // Queued requests hold the old permits, so a new semaphore lets as
+        // many requests run next to them. Keep it unless the limit changed.
+        if max_concurrent != self.max_concurrent {
+            self.permits = Arc::new(Semaphore::new(max_concurrent));
+            self.max_concurrent = max_concurrent;
+        }
+        self.max_per_minute = max_per_minute.max(1) as usize;
+    }
+
+    /// Id for a new request. Results carrying an older id are stale.
+    pub fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub fn is_current(&self, request_id: u64) -> bool {
+        request_id == self.next_id
+    }
+
+    /// Runs `request` once a concurrency permit and a rate limit slot are free.
+    pub fn run<F: Future>(&self, request: F) -> impl Future<Output = F::Output> + use<F> {
+        let permits = self.permits.clone();
+        let starts = self.starts.clone();
+        let max_per_minute = self.max_per_minute;
+
+        async move {
+            let _permit = permits.acquire_owned().await.expect("request queue closed");
+
+            let start = {
+                let mut starts = starts.lock().await;
+
+                while starts.len() > max_per_minute {
+                    starts.pop_front();
+                }
+
+                // With a full minute, wait until the oldest start is a minute ago.
+                let start = match starts.front() {
+                    Some(oldest) if starts.len() == max_per_minute => {
+                        (*oldest + Duration::from_secs(60)).max(Instant::now())
+                    }
+                    _ => Instant::now(),
+                };
+
+                if starts.len() == max_per_minute {
+                    starts.pop_front();
+                }
+                starts.push_back(start);
+                start
+            };
+            tokio::time::sleep_until(start).await;
+
+            request.await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures_util::future::join_all;
+
+    use super::*;
+    use crate::gql::GET_ALL_LOTS;
+    use crate::search::MarketQuery;
+
+    #[test]
+    fn prune_removes_expired_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("0123456789abcdef.ron");
+        std::fs::write(&file, "()").unwrap();
+
+        let mut cache = MarketCache::new(dir.path().to_path_buf(), Duration::from_secs(60));
+        cache.prune();
+        assert!(file.exists());
+
+        cache.ttl = Duration::ZERO;
+        std::thread::sleep(Duration::from_millis(20));
+        cache.prune();
+        assert!(!file.exists());
+    }
+
+    fn vars(name: &str) -> Vars {
+        let query = MarketQuery {
+            names: vec![name.to_string()],
+            ..MarketQuery::default()
+        };
+        query.generate_gql_vars(10).remove(0).1
+    }
+
+    fn record(dir: &Path, vars: &Vars, recorded_vars: &Vars) {
+        let recording = serde_json::json!({
+            "vars": recorded_vars,
+            "data": {
+                "lots": {
+                    "Lots": [],
+                    "Pagination": {
+                        "total": 0,
+                        "currentPage": 1,
+                        "nextPageExists": false,
+                        "__typename": "Pagination",
+                    },
+                    "__typename": "LotsResponse",
+                },
+            },
+        });
+        let file = dir.join(format!("{}.json", key_hash(&vars_key(vars))));
+
+        std::fs::write(file, recording.to_string()).unwrap();
+    }
+
+    #[test]
+    fn cache_key_depends_on_server_language_and_player() {
+        let vars = vars("Dragon");
+        let key = MarketCache::key("https://a", "pl", Some("secret"), &vars);
+
+        for other in [
+            MarketCache::key("https://b", "pl", Some("secret"), &vars),
+            MarketCache::key("https://a", "en", Some("secret"), &vars),
+            MarketCache::key("https://a", "pl", Some("other"), &vars),
+            MarketCache::key("https://a", "pl", None, &vars),
+        ] {
+            assert_ne!(key, other);
+        }
+        assert!(!key.contains("secret"));
+    }
+
+    #[test]
+    fn key_hash_is_stable() {
+        assert_eq!(
+            key_hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_answers_from_matching_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = Traffic::Replay(dir.path().to_path_buf());
+        let client = Client::new("http://localhost:9");
+
+        record(dir.path(), &vars("Dragon"), &vars("Dragon"));
+        let data = replay
+            .clone()
+            .query(client.clone(), GET_ALL_LOTS, vars("Dragon"))
+            .await;
+        assert!(data.is_some_and(|data| data.lots.lots.is_empty()));
+
+        // A recording stored under the hash of other variables is not used.
+        record(dir.path(), &vars("Pad"), &vars("Bone"));
+        assert!(
+            replay
+                .query(client, GET_ALL_LOTS, vars("Pad"))
+                .await
+                .is_none()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queue_starts_at_most_the_limit_per_minute() {
+        let queue = RequestQueue::new(10, 2);
+        let start = Instant::now();
+
+        let starts = join_all((0..5).map(|_| queue.run(async { Instant::now() }))).await;
+        let offsets: Vec<u64> = starts
+            .iter()
+            .map(|started| (*started - start).as_secs())
+            .collect();
+
+        assert_eq!(offsets, [0, 0, 60, 60, 120]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queue_limits_requests_in_flight() {
+        let queue = RequestQueue::new(2, 6000);
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+
+        join_all((0..5).map(|_| {
+            let in_flight = in_flight.clone();
+            let most = most.clone();
+
+            queue.run(async move {
+                let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                most.fetch_max(running, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+            })
+        }))
+        .await;
+
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn unchanged_limits_keep_the_permits() {
+        let mut queue = RequestQueue::new(2, 30);
+        let permits = queue.permits.clone();
+
+        queue.set_limits(2, 60);
+        assert!(Arc::ptr_eq(&permits, &queue.permits));
+        assert_eq!(queue.max_per_minute, 60);
+
+        queue.set_limits(3, 60);
+        assert!(!Arc::ptr_eq(&permits, &queue.permits));
+    }
+
+    #[test]
+    fn concurrency_limit_is_clamped() {
+        let mut queue = RequestQueue::new(0, 30);
+        assert_eq!(queue.max_concurrent, 1);
+
+        queue.set_limits(usize::MAX, 30);
+        assert_eq!(queue.max_concurrent, MAX_CONCURRENT_REQUESTS);
+    }
+
+    #[test]
+    fn newer_requests_supersede_older_ones() {
+        let mut queue = RequestQueue::new(2, 30);
+
+        let first = queue.next_id();
+        assert!(queue.is_current(first));
+
+        let second = queue.next_id();
+        assert!(!queue.is_current(first));
+        assert!(queue.is_current(second));
+    }
+}
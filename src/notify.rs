@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use futures_util::{SinkExt, Stream, StreamExt};
+use tokio::sync::OnceCell;
+use zbus::{Connection, zvariant::Value};
+
+const APP_NAME: &str = "Dream Collections";
+const APP_ICON: &str = "dev.heppen.dream.collections";
+/// Action the server invokes when the notification itself is clicked.
+const DEFAULT_ACTION: &str = "default";
+
+/// Desktop notification service from the freedesktop.org specification.
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+}
+
+static CONNECTION: OnceCell<Option<Connection>> = OnceCell::const_new();
+
+/// Session bus connection shared by sent notifications and their clicks.
+///
+/// The bus comes from `DBUS_SESSION_BUS_ADDRESS`, so a private
+/// `dbus-daemon --session` can stand in for the desktop one.
+async fn connection() -> Option<&'static Connection> {
+    CONNECTION
+        .get_or_init(|| async {
+            Connection::session()
+                .await
+                .inspect_err(|err| eprintln!("failed to connect to the session bus: {err}"))
+                .ok()
+        })
+        .await
+        .as_ref()
+}
+
+/// Shows a desktop notification and returns its id.
+pub async fn send(summary: String, body: String) -> Option<u32> {
+    let result = async {
+        let proxy = NotificationsProxy::new(connection().await?).await.ok()?;
+
+        Some(
+            proxy
+                .notify(
+                    APP_NAME,
+                    0,
+                    APP_ICON,
+                    &summary,
+                    &body,
+                    &[DEFAULT_ACTION, "Pokaż"],
+                    HashMap::new(),
+                    -1,
+                )
+                .await,
+        )
+    }
+    .await?;
+
+    result
+        .inspect_err(|err| eprintln!("failed to send notification: {err}"))
+        .ok()
+}
+
+/// Ids of notifications the player clicked.
+pub fn clicks() -> impl Stream<Item = u32> {
+    iced::stream::channel(16, |mut output| async move {
+        let Some(connection) = connection().await else {
+            return;
+        };
+        let Ok(proxy) = NotificationsProxy::new(connection).await else {
+            return;
+        };
+        let Ok(mut actions) = proxy.receive_action_invoked().await else {
+            return;
+        };
+
+        while let Some(signal) = actions.next().await {
+            if let Ok(args) = signal.args()
+                && args.action_key == DEFAULT_ACTION
+                && output.send(args.id).await.is_err()
+            {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Child, Command, Stdio},
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use zbus::SignalContext;
+
+    use super::*;
+
+    const PATH: &str = "/org/freedesktop/Notifications";
+
+    /// Private session bus, stopped when dropped.
+    struct Bus(Child);
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    /// Summary, body and actions of each received notification.
+    type Sent = Vec<(String, String, Vec<String>)>;
+
+    /// Notification server that remembers what it was sent.
+    #[derive(Default)]
+    struct Server {
+        sent: Arc<Mutex<Sent>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Notifications")]
+    impl Server {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            _app_name: &str,
+            _replaces_id: u32,
+            _app_icon: &str,
+            summary: &str,
+            body: &str,
+            actions: Vec<String>,
+            _hints: HashMap<&str, Value<'_>>,
+            _expire_timeout: i32,
+        ) -> u32 {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push((summary.to_string(), body.to_string(), actions));
+            sent.len() as u32
+        }
+
+        #[zbus(signal)]
+        async fn action_invoked(
+            ctxt: &SignalContext<'_>,
+            id: u32,
+            action_key: &str,
+        ) -> zbus::Result<()>;
+    }
+
+    #[tokio::test]
+    async fn sends_notifications_and_reports_clicks() {
+        let spawned = Command::new("dbus-daemon")
+            .args(["--session", "--print-address", "--nofork"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = spawned else {
+            eprintln!("dbus-daemon not found, skipping");
+            return;
+        };
+        let mut address = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let _bus = Bus(child);
+
+        let server = Server::default();
+        let sent = server.sent.clone();
+        let server = zbus::connection::Builder::address(address.trim())
+            .unwrap()
+            .name("org.freedesktop.Notifications")
+            .unwrap()
+            .serve_at(PATH, server)
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        let client = zbus::connection::Builder::address(address.trim())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        assert!(CONNECTION.set(Some(client)).is_ok());
+
+        assert_eq!(
+            send("Tytuł".to_string(), "Treść".to_string()).await,
+            Some(1)
+        );
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [(
+                "Tytuł".to_string(),
+                "Treść".to_string(),
+                vec![DEFAULT_ACTION.to_string(), "Pokaż".to_string()]
+            )]
+        );
+
+        // The click stream subscribes in the background, so keep clicking
+        // until it reports one. Other actions are not clicks.
+        let clicking = tokio::spawn(async move {
+            let ctxt = SignalContext::new(&server, PATH).unwrap();
+
+            loop {
+                Server::action_invoked(&ctxt, 2, "other").await.unwrap();
+                Server::action_invoked(&ctxt, 1, DEFAULT_ACTION)
+                    .await
+                    .unwrap();
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        });
+
+        let mut clicks = std::pin::pin!(clicks());
+        let click = tokio::time::timeout(Duration::from_secs(5), clicks.next()).await;
+        clicking.abort();
+
+        assert_eq!(click, Ok(Some(1)));
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::items::ItemOptionType;
+
+/// A market lot that could fill options of one collection item.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub lot_id: String,
+    /// Index of the collection item the lot belongs to.
+    pub item: usize,
+    pub options: BTreeSet<ItemOptionType>,
+    /// Price per currency title.
+    pub prices: BTreeMap<String, i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlanLine {
+    pub item: usize,
+    pub lot_id: String,
+    pub options: Vec<ItemOptionType>,
+    pub cost: i64,
+}
+
+/// Cheapest set of lots priced in one currency that covers the missing options.
+#[derive(Clone, Debug)]
+pub struct PurchasePlan {
+    pub currency: String,
+    pub lines: Vec<PlanLine>,
+    pub total: i64,
+    /// Missing options no lot in this currency carries.
+    pub uncovered: Vec<(usize, ItemOptionType)>,
+}
+
+/// Builds one plan per currency seen among the candidates.
+///
+/// `missing` lists, per collection item, the options still to collect.
+pub fn plan(
+    missing: &[(usize, BTreeSet<ItemOptionType>)],
+    candidates: &[Candidate],
+) -> Vec<PurchasePlan> {
+    let currencies: BTreeSet<&String> = candidates
+        .iter()
+        .flat_map(|candidate| candidate.prices.keys())
+        .collect();
+
+    currencies
+        .into_iter()
+        .map(|currency| {
+            let mut plan = PurchasePlan {
+                currency: currency.clone(),
+                lines: vec![],
+                total: 0,
+                uncovered: vec![],
+            };
+
+            for (item, options) in missing {
+                let priced: Vec<(&Candidate, i64)> = candidates
+                    .iter()
+                    .filter(|candidate| candidate.item == *item)
+                    .filter_map(|candidate| Some((candidate, *candidate.prices.get(currency)?)))
+                    .collect();
+
+                let (lines, uncovered) = cover_item(*item, options, &priced);
+
+                plan.total += lines.iter().map(|line| line.cost).sum::<i64>();
+                plan.lines.extend(lines);
+                plan.uncovered
+                    .extend(uncovered.into_iter().map(|option| (*item, option)));
+            }
+
+            plan
+        })
+        .collect()
+}
+
+/// Exact weighted set cover over the missing options of one item.
+fn cover_item(
+    item: usize,
+    missing: &BTreeSet<ItemOptionType>,
+    priced: &[(&Candidate, i64)],
+) -> (Vec<PlanLine>, Vec<ItemOptionType>) {
+    let options: Vec<&ItemOptionType> = missing.iter().collect();
+
+    let mask_of = |candidate: &Candidate| {
+        options
+            .iter()
+            .enumerate()
+            .filter(|(_, option)| candidate.options.contains(option))
+            .fold(0usize, |mask, (bit, _)| mask | 1 << bit)
+    };
+
+    let masks: Vec<usize> = priced
+        .iter()
+        .map(|(candidate, _)| mask_of(candidate))
+        .collect();
+    let coverable = masks.iter().fold(0, |all, mask| all | mask);
+
+    let uncovered = options
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| coverable & (1 << bit) == 0)
+        .map(|(_, option)| (*option).clone())
+        .collect();
+
+    // best[mask] = cheapest cost to cover `mask`, with the lot that completed it.
+    let mut best: Vec<Option<(i64, usize, usize)>> = vec![None; coverable + 1];
+    best[0] = Some((0, usize::MAX, 0));
+
+    for mask in 0..=coverable {
+        let Some((cost, _, _)) = best[mask] else {
+            continue;
+        };
+
+        for (lot, lot_mask) in masks.iter().enumerate() {
+            let next = mask | lot_mask;
+
+            if next == mask {
+                continue;
+            }
+
+            let next_cost = cost + priced[lot].1;
+
+            if best[next].is_none_or(|(known, _, _)| next_cost < known) {
+                best[next] = Some((next_cost, lot, mask));
+            }
+        }
+    }
+
+    let mut lines = vec![];
+    let mut mask = coverable;
+
+    while mask != 0 {
+        let Some((_, lot, previous)) = best[mask] else {
+            break;
+        };
+        let (candidate, cost) = priced[lot];
+
+        lines.push(PlanLine {
+            item,
+            lot_id: candidate.lot_id.clone(),
+            options: options
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| (mask & !previous) & (1 << bit) != 0)
+                .map(|(_, option)| (*option).clone())
+                .collect(),
+            cost,
+        });
+        mask = previous;
+    }
+
+    lines.reverse();
+
+    (lines, uncovered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(lot_id: &str, options: &[ItemOptionType], price: i64) -> Candidate {
+        Candidate {
+            lot_id: lot_id.to_string(),
+            item: 0,
+            options: options.iter().cloned().collect(),
+            prices: BTreeMap::from([("Zen".to_string(), price)]),
+        }
+    }
+
+    fn lots(plan: &PurchasePlan) -> Vec<&str> {
+        plan.lines.iter().map(|line| line.lot_id.as_str()).collect()
+    }
+
+    #[test]
+    fn single_lot_covers_everything() {
+        let missing = [(0, BTreeSet::from([ItemOptionType::DD, ItemOptionType::MH]))];
+        let candidates = [
+            candidate("both", &[ItemOptionType::DD, ItemOptionType::MH], 100),
+            candidate("dd", &[ItemOptionType::DD], 80),
+        ];
+
+        let plans = plan(&missing, &candidates);
+
+        assert_eq!(plans.len(), 1);
+        assert_eq!(lots(&plans[0]), ["both"]);
+        assert_eq!(plans[0].total, 100);
+        assert!(plans[0].uncovered.is_empty());
+    }
+
+    #[test]
+    fn overlapping_lots_pick_the_cheapest_cover() {
+        let missing = [(
+            0,
+            BTreeSet::from([ItemOptionType::MH, ItemOptionType::SD, ItemOptionType::DD]),
+        )];
+        let candidates = [
+            candidate("mh_sd", &[ItemOptionType::MH, ItemOptionType::SD], 60),
+            candidate("sd_dd", &[ItemOptionType::SD, ItemOptionType::DD], 60),
+            candidate("mh", &[ItemOptionType::MH], 30),
+            candidate(
+                "all",
+                &[ItemOptionType::MH, ItemOptionType::SD, ItemOptionType::DD],
+                100,
+            ),
+        ];
+
+        let plans = plan(&missing, &candidates);
+
+        assert_eq!(plans[0].total, 90);
+        let mut chosen = lots(&plans[0]);
+        chosen.sort();
+        assert_eq!(chosen, ["mh", "sd_dd"]);
+    }
+
+    #[test]
+    fn option_nobody_sells_is_uncovered() {
+        let missing = [(0, BTreeSet::from([ItemOptionType::DD, ItemOptionType::Zen]))];
+        let candidates = [candidate("dd", &[ItemOptionType::DD], 50)];
+
+        let plans = plan(&missing, &candidates);
+
+        assert_eq!(lots(&plans[0]), ["dd"]);
+        assert_eq!(plans[0].uncovered, [(0, ItemOptionType::Zen)]);
+    }
+
+    #[test]
+    fn plans_are_per_currency() {
+        let missing = [(0, BTreeSet::from([ItemOptionType::DD]))];
+        let mut credits = candidate("credits", &[ItemOptionType::DD], 5);
+        credits.prices = BTreeMap::from([("Credits".to_string(), 5)]);
+        let candidates = [candidate("zen", &[ItemOptionType::DD], 50), credits];
+
+        let plans = plan(&missing, &candidates);
+
+        let currencies: Vec<&str> = plans.iter().map(|plan| plan.currency.as_str()).collect();
+        assert_eq!(currencies, ["Credits", "Zen"]);
+        assert_eq!(lots(&plans[0]), ["credits"]);
+        assert_eq!(lots(&plans[1]), ["zen"]);
+    }
+}
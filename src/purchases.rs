@@ -0,0 +1,63 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::items::ItemOptionType;
+use crate::store;
+
+/// A lot the player bought, with the options it added to the collection.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Purchase {
+    pub lot_id: String,
+    /// Title of the collection item, e.g. "Dragon Helm".
+    pub item: String,
+    pub options: Vec<ItemOptionType>,
+    /// Listed price per currency title.
+    pub prices: BTreeMap<String, i64>,
+    pub purchased_at: SystemTime,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PurchaseLog {
+    pub purchases: Vec<Purchase>,
+}
+
+impl PurchaseLog {
+    pub fn path(app_dir: &Path) -> PathBuf {
+        app_dir.join("purchases.ron")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        store::load(path)
+    }
+
+    pub fn save(&self, path: &Path) {
+        store::save(self, path);
+    }
+}
+
+/// UTC calendar date, e.g. "2024-05-17".
+pub fn format_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86_400;
+
+    // Civil date from days since 1970-01-01, see Howard Hinnant's `civil_from_days`.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
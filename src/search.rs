@@ -0,0 +1,420 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::gql::{self, Filter, LotField, Sort, SortType, Vars};
+use crate::items::{ItemOptionType, ItemOptions, ItemType, MAX_OPTION_LEVEL};
+use crate::store;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Also stands in for the gear score order, which the market never confirmed.
+    #[default]
+    #[serde(alias = "GearScoreDesc")]
+    PriceAsc,
+    PriceDesc,
+}
+
+impl Display for SortOrder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sort_str = match self {
+            SortOrder::PriceAsc => "Cena rosnąco",
+            SortOrder::PriceDesc => "Cena malejąco",
+        };
+        write!(f, "{}", sort_str)
+    }
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 2] = [SortOrder::PriceAsc, SortOrder::PriceDesc];
+
+    pub fn as_sort(&self) -> Sort {
+        let (field, type_) = match self {
+            SortOrder::PriceAsc => (LotField::MinPrice, SortType::Asc),
+            SortOrder::PriceDesc => (LotField::MinPrice, SortType::Desc),
+        };
+
+        Sort { field, type_ }
+    }
+
+    /// Orders lots merged from several queries the way the market orders the
+    /// lots of one query. Prices in `currency` count when it is set, lots
+    /// without a price go last.
+    pub fn compare(&self, a: &gql::Item, b: &gql::Item, currency: Option<&str>) -> Ordering {
+        let min_price = |lot: &gql::Item| {
+            lot.prices
+                .iter()
+                .filter(|price| {
+                    currency.is_none_or(|code| price.currency.code.as_deref() == Some(code))
+                })
+                .filter_map(|price| price.value)
+                .min()
+        };
+
+        match (min_price(a), min_price(b)) {
+            (Some(a), Some(b)) => match self {
+                SortOrder::PriceAsc => a.cmp(&b),
+                SortOrder::PriceDesc => b.cmp(&a),
+            },
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        }
+    }
+}
+
+/// Accepted option levels, both ends included.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LevelRange {
+    pub min: u8,
+    pub max: u8,
+}
+
+impl Default for LevelRange {
+    fn default() -> Self {
+        LevelRange {
+            min: 0,
+            max: MAX_OPTION_LEVEL,
+        }
+    }
+}
+
+impl LevelRange {
+    fn contains(&self, level: u8) -> bool {
+        (self.min..=self.max).contains(&level)
+    }
+}
+
+/// A market search definition, independent of what the player owns.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SavedSearch {
+    pub name: String,
+    /// Set name for armor, full item name for other gear.
+    pub set: Option<String>,
+    pub item_type: Option<ItemType>,
+    pub options: ItemOptions,
+    /// Levels of the wanted options.
+    #[serde(default)]
+    pub levels: LevelRange,
+    pub sort: SortOrder,
+    /// Only show lots priced in this currency code.
+    pub currency: Option<String>,
+}
+
+impl SavedSearch {
+    /// Variables for each wanted option. The market requires every option in
+    /// a filter at once, and a lot with all of them is rare, so the options
+    /// are queried one at a time. Without wanted options this is one query.
+    pub fn generate_gql_vars(&self, page_size: u32) -> Vec<(Option<ItemOptionType>, Vars)> {
+        let mut options: Vec<Option<ItemOptionType>> = self.wanted_options().map(Some).collect();
+
+        if options.is_empty() {
+            options.push(None);
+        }
+
+        options
+            .into_iter()
+            .map(|option| {
+                let vars = Vars {
+                    filter: Some(filter(
+                        option.iter(),
+                        self.levels,
+                        self.item_type.iter().copied(),
+                        self.set.clone(),
+                    )),
+                    limit: Some(page_size),
+                    offset: Some(0),
+                    sort: Some(self.sort.as_sort()),
+                };
+                (option, vars)
+            })
+            .collect()
+    }
+
+    pub fn wanted_options(&self) -> impl Iterator<Item = ItemOptionType> + '_ {
+        self.options
+            .0
+            .iter()
+            .filter(|(_, has_option)| **has_option)
+            .map(|(option, _)| option.clone())
+    }
+
+    /// Key of the search's prices in the price history.
+    pub fn history_key(&self) -> String {
+        let options: Vec<ItemOptionType> = self.wanted_options().collect();
+
+        ron::to_string(&(&self.set, self.item_type, options)).unwrap_or_default()
+    }
+
+    /// Changes the item type, resetting the options to the new type's vocabulary.
+    pub fn set_item_type(&mut self, item_type: ItemType) {
+        if self.item_type != Some(item_type) {
+            self.item_type = Some(item_type);
+            self.options = ItemOptions::for_type(item_type);
+        }
+    }
+}
+
+/// Market search across every set, where each criterion is optional.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MarketQuery {
+    /// Set or item names, a lot matching any of them is returned.
+    pub names: Vec<String>,
+    /// Item types, a lot of any of them is returned.
+    pub item_types: Vec<ItemType>,
+    /// Options every returned lot carries.
+    pub options: BTreeSet<ItemOptionType>,
+    pub sort: SortOrder,
+}
+
+impl MarketQuery {
+    /// Variables for each name, since the market filters by a single name.
+    pub fn generate_gql_vars(&self, page_size: u32) -> Vec<(Option<String>, Vars)> {
+        let names: Vec<Option<String>> = if self.names.is_empty() {
+            vec![None]
+        } else {
+            self.names.iter().cloned().map(Some).collect()
+        };
+
+        names
+            .into_iter()
+            .map(|name| {
+                let vars = Vars {
+                    filter: Some(filter(
+                        self.options.iter(),
+                        LevelRange::default(),
+                        self.item_types.iter().copied(),
+                        name.clone(),
+                    )),
+                    limit: Some(page_size),
+                    offset: Some(0),
+                    sort: Some(self.sort.as_sort()),
+                };
+                (name, vars)
+            })
+            .collect()
+    }
+}
+
+fn filter<'a>(
+    options: impl Iterator<Item = &'a ItemOptionType>,
+    levels: LevelRange,
+    item_types: impl Iterator<Item = ItemType>,
+    name: Option<String>,
+) -> Filter {
+    // Leaving out a type without a known market value would drop its lots,
+    // so such a type lifts the type filter altogether.
+    let item_types: Option<Vec<String>> = item_types
+        .map(|item_type| item_type.gql_type().map(str::to_string))
+        .collect();
+
+    let mut filter = Filter {
+        type_: item_types.filter(|item_types| !item_types.is_empty()),
+        name,
+        iml: None,
+        imsd: None,
+        dd: None,
+        rd: None,
+        dsr: None,
+        izdr: None,
+    };
+
+    for spec in options.map(ItemOptionType::spec) {
+        if let Some(field) = spec.filter {
+            *field(&mut filter) = Some(
+                spec.levels
+                    .iter()
+                    .copied()
+                    .filter(|level| levels.contains(*level))
+                    .map(i64::from)
+                    .collect(),
+            );
+        }
+    }
+
+    filter
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SavedSearches {
+    pub searches: Vec<SavedSearch>,
+}
+
+impl SavedSearches {
+    pub fn path(app_dir: &Path) -> PathBuf {
+        app_dir.join("searches.ron")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        store::load(path)
+    }
+
+    pub fn save(&self, path: &Path) {
+        store::save(self, path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::{Item, OPTION_SCHEMA};
+
+    #[test]
+    fn every_option_filters_on_its_own_field() {
+        let mut fields = BTreeSet::new();
+
+        for spec in OPTION_SCHEMA {
+            let query = MarketQuery {
+                item_types: vec![ItemType::Helm],
+                options: BTreeSet::from([spec.option.clone()]),
+                ..MarketQuery::default()
+            };
+            let (_, vars) = query.generate_gql_vars(10).remove(0);
+            let json = serde_json::to_value(&vars).unwrap();
+            let filter = json["filter"].as_object().unwrap();
+
+            assert_eq!(filter["type"], serde_json::json!(["helm"]));
+
+            let options: Vec<(&String, &serde_json::Value)> = filter
+                .iter()
+                .filter(|(field, _)| *field != "type")
+                .collect();
+
+            if spec.filter.is_none() {
+                assert!(options.is_empty(), "{} is sent", spec.label);
+                continue;
+            }
+
+            assert_eq!(options.len(), 1, "{} is not sent", spec.label);
+            assert_eq!(options[0].1, &serde_json::json!(spec.levels));
+            assert!(
+                fields.insert(options[0].0.clone()),
+                "{} shares {}",
+                spec.label,
+                options[0].0
+            );
+        }
+    }
+
+    #[test]
+    fn saved_search_queries_each_wanted_option() {
+        let mut search = Item::new("Dragon".to_string(), ItemType::Helm)
+            .to_search(SortOrder::default())
+            .unwrap();
+
+        let fields = |search: &SavedSearch| -> Vec<(Option<ItemOptionType>, usize)> {
+            search
+                .generate_gql_vars(10)
+                .into_iter()
+                .map(|(option, vars)| {
+                    let json = serde_json::to_value(&vars).unwrap();
+                    (option, json["filter"].as_object().unwrap().len())
+                })
+                .collect()
+        };
+
+        // Type, name and one option in each query.
+        let queries = fields(&search);
+        assert_eq!(queries.len(), 6);
+        assert!(
+            queries
+                .iter()
+                .all(|(option, len)| option.is_some() && *len == 3)
+        );
+
+        search
+            .options
+            .0
+            .values_mut()
+            .for_each(|wanted| *wanted = false);
+        assert_eq!(fields(&search), vec![(None, 2)]);
+    }
+
+    #[test]
+    fn saved_search_sends_only_levels_in_its_range() {
+        let mut search = Item::new("Dragon".to_string(), ItemType::Helm)
+            .to_search(SortOrder::default())
+            .unwrap();
+        search.levels = LevelRange { min: 2, max: 3 };
+
+        let (_, vars) = search.generate_gql_vars(10).remove(0);
+        let json = serde_json::to_value(&vars).unwrap();
+
+        assert_eq!(json["filter"]["iml"], serde_json::json!([2, 3]));
+    }
+
+    #[test]
+    fn saved_searches_without_levels_or_with_gear_score_order_still_load() {
+        let search: SavedSearch = ron::from_str(
+            r#"(name: "Dragon Helm", set: Some("Dragon"), item_type: Some(Helm),
+                options: ({MH: true}), sort: GearScoreDesc, currency: None)"#,
+        )
+        .unwrap();
+
+        assert_eq!(search.levels, LevelRange::default());
+        assert_eq!(search.sort, SortOrder::PriceAsc);
+    }
+
+    #[test]
+    fn merged_lots_are_ordered_by_price() {
+        let lot = |prices: &[(&str, i64)]| -> gql::Item {
+            let prices: Vec<_> = prices
+                .iter()
+                .map(
+                    |(code, value)| serde_json::json!({"value": value, "Currency": {"code": code}}),
+                )
+                .collect();
+
+            serde_json::from_value(serde_json::json!({"Prices": prices})).unwrap()
+        };
+        let mut lots = [
+            lot(&[("ZEN", 300)]),
+            lot(&[]),
+            lot(&[("ZEN", 100), ("CREDIT", 900)]),
+            lot(&[("CREDIT", 5)]),
+        ];
+
+        let prices = |lots: &[gql::Item]| -> Vec<Option<i64>> {
+            lots.iter()
+                .map(|lot| lot.prices.first().and_then(|price| price.value))
+                .collect()
+        };
+
+        lots.sort_by(|a, b| SortOrder::PriceAsc.compare(a, b, None));
+        assert_eq!(prices(&lots), [Some(5), Some(100), Some(300), None]);
+
+        lots.sort_by(|a, b| SortOrder::PriceDesc.compare(a, b, Some("ZEN")));
+        assert_eq!(prices(&lots), [Some(300), Some(100), Some(5), None]);
+    }
+
+    #[test]
+    fn gear_without_a_market_type_is_searched_by_name() {
+        let query = MarketQuery {
+            names: vec!["Legendary Staff".to_string()],
+            item_types: vec![ItemType::Helm, ItemType::Staff],
+            ..MarketQuery::default()
+        };
+        let (_, vars) = query.generate_gql_vars(10).remove(0);
+        let json = serde_json::to_value(&vars).unwrap();
+
+        assert_eq!(
+            json["filter"],
+            serde_json::json!({"name": "Legendary Staff"})
+        );
+    }
+
+    #[test]
+    fn unset_filters_are_left_out() {
+        let (_, vars) = MarketQuery::default().generate_gql_vars(10).remove(0);
+        let json = serde_json::to_value(&vars).unwrap();
+
+        assert_eq!(json["filter"], serde_json::json!({}));
+        assert_eq!(
+            json["sort"],
+            serde_json::json!({"field": "LOT_FIELD_MIN_PRICE", "type": "SORT_TYPE_ASC"})
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use gql_client::Client;
+
+/// Marketplace session token, stored in a file only the current user can read.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    pub token: Option<String>,
+}
+
+impl Session {
+    pub fn path(app_dir: &Path) -> PathBuf {
+        app_dir.join("session")
+    }
+
+    pub fn load(path: &Path) -> Self {
+        let token = std::fs::read_to_string(path)
+            .ok()
+            .map(|data| data.trim().to_string())
+            .filter(|token| !token.is_empty());
+
+        Session { token }
+    }
+
+    pub fn save(&self, path: &Path) {
+        let Some(token) = self.token.as_ref() else {
+            if path.exists()
+                && let Err(err) = std::fs::remove_file(path)
+            {
+                eprintln!("failed to remove session: {err}");
+            }
+            return;
+        };
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let result = options.open(path).and_then(|mut file| {
+            // The mode only applies on creation, so tighten an existing file too.
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+            }
+            file.write_all(token.as_bytes())
+        });
+
+        if let Err(err) = result {
+            eprintln!("failed to save session: {err}");
+        }
+    }
+
+    pub fn is_logged_in(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// GraphQL client that sends the session token, if any.
+    pub fn client(&self, endpoint: &str, language: &str) -> Client {
+        let mut headers = HashMap::from([("Accept-Language", language.to_string())]);
+
+        if let Some(token) = self.token.as_ref() {
+            headers.insert("Authorization", format!("Bearer {token}"));
+        }
+
+        Client::new_with_headers(endpoint, headers)
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+
+use iced::Theme;
+use serde::{Deserialize, Serialize};
+
+use crate::market::{
+    DEFAULT_CACHE_TTL, DEFAULT_MAX_CONCURRENT_REQUESTS, DEFAULT_MAX_REQUESTS_PER_MINUTE,
+    MAX_CONCURRENT_REQUESTS,
+};
+use crate::search::SortOrder;
+use crate::store;
+
+pub const DEFAULT_ENDPOINT: &str = "https://mudream.online/api/graphql";
+pub const DEFAULT_LOT_URL_TEMPLATE: &str = "https://mudream.online/market/lot/{id}";
+pub const DEFAULT_LANGUAGE: &str = "pl";
+pub const DEFAULT_PAGE_SIZE: u32 = 200;
+
+/// Theme picked by the player.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum ThemeChoice {
+    /// Light or dark, following the desktop.
+    #[default]
+    System,
+    /// One of `iced::Theme::ALL`, by name.
+    Iced(String),
+}
+
+impl Display for ThemeChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeChoice::System => write!(f, "Systemowy"),
+            ThemeChoice::Iced(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl ThemeChoice {
+    pub fn all() -> Vec<ThemeChoice> {
+        std::iter::once(ThemeChoice::System)
+            .chain(
+                Theme::ALL
+                    .iter()
+                    .map(|theme| ThemeChoice::Iced(theme.to_string())),
+            )
+            .collect()
+    }
+
+    pub fn theme(&self) -> Theme {
+        match self {
+            ThemeChoice::System => match dark_light::detect() {
+                dark_light::Mode::Dark => Theme::Dark,
+                dark_light::Mode::Light | dark_light::Mode::Default => Theme::Light,
+            },
+            ThemeChoice::Iced(name) => Theme::ALL
+                .iter()
+                .find(|theme| theme.to_string() == *name)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Application preferences, stored next to the collections.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: ThemeChoice,
+    /// Market GraphQL endpoint.
+    pub endpoint: String,
+    /// Marketplace page of a lot, `{id}` is replaced with the lot id.
+    pub lot_url_template: String,
+    /// Sent as `Accept-Language` with market requests.
+    pub language: String,
+    /// Lots requested per market query.
+    pub page_size: u32,
+    /// Sort order of searches started from collection items.
+    pub default_sort: SortOrder,
+    pub cache_ttl_secs: u64,
+    pub max_concurrent_requests: usize,
+    pub max_requests_per_minute: u32,
+    /// Desktop notifications when market tasks finish in the background.
+    pub notifications: bool,
+    /// Notify about lots priced at or below the value set for their currency
+    /// code. Prices in different currencies are not comparable.
+    pub alert_prices: BTreeMap<String, i64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            theme: ThemeChoice::default(),
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            lot_url_template: DEFAULT_LOT_URL_TEMPLATE.to_string(),
+            language: DEFAULT_LANGUAGE.to_string(),
+            page_size: DEFAULT_PAGE_SIZE,
+            default_sort: SortOrder::default(),
+            cache_ttl_secs: DEFAULT_CACHE_TTL.as_secs(),
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            max_requests_per_minute: DEFAULT_MAX_REQUESTS_PER_MINUTE,
+            notifications: true,
+            alert_prices: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn path(app_dir: &Path) -> PathBuf {
+        app_dir.join("settings.ron")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        store::load(path)
+    }
+
+    pub fn save(&self, path: &Path) {
+        store::save(self, path);
+    }
+}
+
+/// Settings as typed on the settings screen, checked by `validate` on save.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SettingsDraft {
+    pub theme: ThemeChoice,
+    pub endpoint: String,
+    pub lot_url_template: String,
+    pub language: String,
+    pub page_size: String,
+    pub default_sort: SortOrder,
+    pub cache_ttl_secs: String,
+    pub max_concurrent_requests: String,
+    pub max_requests_per_minute: String,
+    pub notifications: bool,
+    /// `CODE=price` pairs separated by commas, empty for no price alerts.
+    pub alert_prices: String,
+}
+
+impl From<&Settings> for SettingsDraft {
+    fn from(settings: &Settings) -> Self {
+        SettingsDraft {
+            theme: settings.theme.clone(),
+            endpoint: settings.endpoint.clone(),
+            lot_url_template: settings.lot_url_template.clone(),
+            language: settings.language.clone(),
+            page_size: settings.page_size.to_string(),
+            default_sort: settings.default_sort,
+            cache_ttl_secs: settings.cache_ttl_secs.to_string(),
+            max_concurrent_requests: settings.max_concurrent_requests.to_string(),
+            max_requests_per_minute: settings.max_requests_per_minute.to_string(),
+            notifications: settings.notifications,
+            alert_prices: settings
+                .alert_prices
+                .iter()
+                .map(|(code, price)| format!("{code}={price}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+impl SettingsDraft {
+    /// Settings from the typed values, or what the player has to correct.
+    pub fn validate(&self) -> Result<Settings, String> {
+        let text = |label: &str, value: &str| {
+            let value = value.trim();
+
+            if value.is_empty() {
+                Err(format!("{label}: pole nie może być puste"))
+            } else {
+                Ok(value.to_string())
+            }
+        };
+        let positive = |label: &str, value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .ok()
+                .filter(|value| *value > 0)
+                .ok_or_else(|| format!("{label}: podaj liczbę większą od zera"))
+        };
+
+        let lot_url_template = text("Adres oferty", &self.lot_url_template)?;
+
+        if !lot_url_template.contains("{id}") {
+            return Err("Adres oferty: brakuje {id}".to_string());
+        }
+
+        let mut alert_prices = BTreeMap::new();
+
+        for pair in self
+            .alert_prices
+            .split(',')
+            .filter(|pair| !pair.trim().is_empty())
+        {
+            let label = "Powiadom o ofertach tańszych niż";
+            let Some((code, price)) = pair.split_once('=') else {
+                return Err(format!("{label}: podaj KOD=cena, np. ZEN=1000000"));
+            };
+            let code = text(label, code)?;
+            let price = positive(label, price)?
+                .try_into()
+                .map_err(|_| format!("{label}: za duża liczba"))?;
+
+            alert_prices.insert(code, price);
+        }
+
+        Ok(Settings {
+            theme: self.theme.clone(),
+            endpoint: text("Adres API", &self.endpoint)?,
+            lot_url_template,
+            language: text("Język odpowiedzi rynku", &self.language)?,
+            page_size: positive("Ofert na zapytanie", &self.page_size)?
+                .try_into()
+                .map_err(|_| "Ofert na zapytanie: za duża liczba".to_string())?,
+            default_sort: self.default_sort,
+            cache_ttl_secs: positive("Ważność pamięci podręcznej", &self.cache_ttl_secs)?,
+            max_concurrent_requests: positive(
+                "Równoczesne zapytania",
+                &self.max_concurrent_requests,
+            )?
+            .try_into()
+            .ok()
+            .filter(|value| *value <= MAX_CONCURRENT_REQUESTS)
+            .ok_or_else(|| format!("Równoczesne zapytania: najwyżej {MAX_CONCURRENT_REQUESTS}"))?,
+            max_requests_per_minute: positive("Zapytań na minutę", &self.max_requests_per_minute)?
+                .try_into()
+                .map_err(|_| "Zapytań na minutę: za duża liczba".to_string())?,
+            notifications: self.notifications,
+            alert_prices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draft_of_settings_validates_to_the_same_settings() {
+        let settings = Settings::default();
+
+        assert_eq!(SettingsDraft::from(&settings).validate(), Ok(settings));
+    }
+
+    #[test]
+    fn draft_rejects_zero_empty_and_garbage() {
+        let draft = SettingsDraft::from(&Settings::default());
+
+        let invalid = [
+            SettingsDraft {
+                page_size: "0".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                max_concurrent_requests: "".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                max_requests_per_minute: "dużo".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                cache_ttl_secs: "-5".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                endpoint: "  ".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                language: String::new(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                lot_url_template: "https://mudream.online/market".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                alert_prices: "ZEN=0".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                alert_prices: format!("ZEN={}", u64::MAX),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                alert_prices: "1000".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                alert_prices: " =1000".to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                max_concurrent_requests: (MAX_CONCURRENT_REQUESTS + 1).to_string(),
+                ..draft.clone()
+            },
+            SettingsDraft {
+                max_concurrent_requests: u64::MAX.to_string(),
+                ..draft.clone()
+            },
+        ];
+
+        for draft in invalid {
+            assert!(draft.validate().is_err(), "{draft:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn draft_trims_values() {
+        let draft = SettingsDraft {
+            page_size: " 50 ".to_string(),
+            alert_prices: " ZEN = 1000000, CREDIT=5 ,".to_string(),
+            ..SettingsDraft::from(&Settings::default())
+        };
+        let settings = draft.validate().unwrap();
+
+        assert_eq!(settings.page_size, 50);
+        assert_eq!(
+            settings.alert_prices,
+            BTreeMap::from([("CREDIT".to_string(), 5), ("ZEN".to_string(), 1000000)])
+        );
+    }
+}
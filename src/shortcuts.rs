@@ -0,0 +1,56 @@
+use iced::keyboard::{Key, Modifiers, key::Named};
+
+/// Keyboard action, independent of the screen it applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    /// Index into `Page::ALL`.
+    SelectClass(usize),
+    NextSet,
+    PreviousSet,
+    NextItem,
+    PreviousItem,
+    /// Toggles the n-th option of the focused item.
+    ToggleOption(usize),
+    Save,
+    SearchFocused,
+    Escape,
+    ToggleCheatSheet,
+}
+
+/// Keys and what they do, shown in the cheat-sheet overlay.
+pub const CHEAT_SHEET: &[(&str, &str)] = &[
+    ("Ctrl+1…7", "Wybierz klasę"),
+    ("PageDown / PageUp", "Następny / poprzedni set"),
+    ("↓ / ↑", "Następny / poprzedni przedmiot"),
+    ("1…9", "Przełącz opcję wybranego przedmiotu"),
+    ("Ctrl+S", "Zapisz kolekcje"),
+    ("Ctrl+F", "Szukaj wybranego przedmiotu"),
+    ("Esc", "Wyczyść oferty / zamknij ściągawkę"),
+    ("F1", "Pokaż / ukryj ściągawkę"),
+];
+
+/// Maps a key press that no widget handled to a shortcut.
+pub fn from_key(key: Key, modifiers: Modifiers) -> Option<Shortcut> {
+    match key.as_ref() {
+        Key::Named(Named::PageDown) => Some(Shortcut::NextSet),
+        Key::Named(Named::PageUp) => Some(Shortcut::PreviousSet),
+        Key::Named(Named::ArrowDown) => Some(Shortcut::NextItem),
+        Key::Named(Named::ArrowUp) => Some(Shortcut::PreviousItem),
+        Key::Named(Named::Escape) => Some(Shortcut::Escape),
+        Key::Named(Named::F1) => Some(Shortcut::ToggleCheatSheet),
+        Key::Character("s") if modifiers.command() => Some(Shortcut::Save),
+        Key::Character("f") if modifiers.command() => Some(Shortcut::SearchFocused),
+        Key::Character(c) => {
+            let digit = c.parse::<usize>().ok().filter(|digit| *digit > 0)?;
+
+            if modifiers.command() {
+                Some(Shortcut::SelectClass(digit - 1))
+            } else if modifiers.is_empty() {
+                Some(Shortcut::ToggleOption(digit - 1))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,65 @@
+use std::{io::ErrorKind, path::Path};
+
+use ron::ser::{PrettyConfig, to_string_pretty};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Reads a RON file, a missing or empty file gives the default value.
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    let data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(T::default()),
+        Err(err) => return Err(format!("Nie można odczytać {}: {err}", path.display())),
+    };
+
+    if data.trim().is_empty() {
+        Ok(T::default())
+    } else {
+        ron::from_str(&data).map_err(|err| format!("Niepoprawny plik {}: {err}", path.display()))
+    }
+}
+
+/// Writes a RON file, unless the file there is unreadable. The player may
+/// still fix such a file by hand, so it is never replaced by defaults.
+pub fn save<T: Serialize + DeserializeOwned + Default>(value: &T, path: &Path) {
+    if let Err(err) = load::<T>(path) {
+        eprintln!("not overwriting {}: {err}", path.display());
+        return;
+    }
+
+    let result = to_string_pretty(value, PrettyConfig::new())
+        .map_err(std::io::Error::other)
+        .and_then(|data| std::fs::write(path, data));
+
+    if let Err(err) = result {
+        eprintln!("failed to save {}: {err}", path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::SavedSearches;
+
+    #[test]
+    fn missing_and_empty_files_are_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("searches.ron");
+
+        assert!(load::<SavedSearches>(&path).unwrap().searches.is_empty());
+
+        std::fs::write(&path, "\n").unwrap();
+        assert!(load::<SavedSearches>(&path).unwrap().searches.is_empty());
+    }
+
+    #[test]
+    fn unreadable_files_are_reported_and_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("searches.ron");
+        std::fs::write(&path, "(searches: [").unwrap();
+
+        assert!(load::<SavedSearches>(&path).is_err());
+
+        save(&SavedSearches::default(), &path);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "(searches: [");
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use iced::window::Position;
+use iced::{Point, Size};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::Page;
+use crate::store;
+
+/// Share of the items and offers columns given to the offers by default.
+pub const DEFAULT_OFFERS_SPLIT: f32 = 0.4;
+/// Smallest window size restored or saved.
+pub const MIN_WINDOW_SIZE: Size = Size::new(480.0, 360.0);
+
+/// Where the player left the window, restored on the next launch.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct UiState {
+    pub width: f32,
+    pub height: f32,
+    /// Window position, `None` centers the window.
+    pub position: Option<(f32, f32)>,
+    pub page: Page,
+    pub set: Option<String>,
+    pub offers_split: f32,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        let size = iced::window::Settings::default().size;
+
+        UiState {
+            width: size.width,
+            height: size.height,
+            position: None,
+            page: Page::DarkWizard,
+            set: None,
+            offers_split: DEFAULT_OFFERS_SPLIT,
+        }
+    }
+}
+
+impl UiState {
+    pub fn path(app_dir: &Path) -> PathBuf {
+        app_dir.join("ui_state.ron")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        store::load(path).map(UiState::clamped)
+    }
+
+    /// The state with a window no smaller than `MIN_WINDOW_SIZE`, so a size
+    /// saved from a minimized window does not open an empty one.
+    fn clamped(self) -> Self {
+        UiState {
+            width: self.width.max(MIN_WINDOW_SIZE.width),
+            height: self.height.max(MIN_WINDOW_SIZE.height),
+            ..self
+        }
+    }
+
+    /// Where to open the window: the saved position when it may be on one
+    /// of the monitors, centered otherwise.
+    pub fn window_position(&self) -> Position {
+        match self.position {
+            Some((x, y)) => {
+                let _ = SAVED_POSITION.set(Point::new(x, y));
+                Position::SpecificWith(restored_position)
+            }
+            None => Position::Centered,
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        store::save(&self.clone().clamped(), path);
+    }
+}
+
+/// Saved window position, read by `restored_position` once the monitor is known.
+static SAVED_POSITION: OnceLock<Point> = OnceLock::new();
+
+fn restored_position(window: Size, monitor: Size) -> Point {
+    fit_on_monitor(SAVED_POSITION.get().copied(), window, monitor)
+}
+
+/// `position` when it is on the primary monitor or where a monitor of the
+/// same size could sit next to it in any direction, the primary monitor
+/// center otherwise. Only the primary monitor is known at startup, so this
+/// keeps positions on other monitors and still catches far off ones, like
+/// the one Windows reports for a minimized window.
+fn fit_on_monitor(position: Option<Point>, window: Size, monitor: Size) -> Point {
+    let centered = Point::new(
+        ((monitor.width - window.width) / 2.0).max(0.0),
+        ((monitor.height - window.height) / 2.0).max(0.0),
+    );
+
+    match position {
+        Some(position)
+            if (-monitor.width..2.0 * monitor.width).contains(&position.x)
+                && (-monitor.height..2.0 * monitor.height).contains(&position.y) =>
+        {
+            position
+        }
+        _ => centered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONITOR: Size = Size::new(1920.0, 1080.0);
+    const WINDOW: Size = Size::new(1024.0, 768.0);
+
+    #[test]
+    fn keeps_a_position_on_any_monitor() {
+        for position in [
+            Point::new(100.0, 50.0),
+            Point::new(1000.0, 100.0),
+            // On a second monitor right of, below, left of or above the primary one.
+            Point::new(2500.0, 100.0),
+            Point::new(100.0, 1200.0),
+            Point::new(-900.0, 100.0),
+            Point::new(100.0, -500.0),
+        ] {
+            assert_eq!(fit_on_monitor(Some(position), WINDOW, MONITOR), position);
+        }
+    }
+
+    #[test]
+    fn centers_a_far_off_position() {
+        let centered = Point::new(448.0, 156.0);
+
+        for position in [
+            Point::new(-32000.0, -32000.0),
+            Point::new(5000.0, 100.0),
+            Point::new(100.0, -1200.0),
+        ] {
+            assert_eq!(fit_on_monitor(Some(position), WINDOW, MONITOR), centered);
+        }
+        assert_eq!(fit_on_monitor(None, WINDOW, MONITOR), centered);
+    }
+
+    #[test]
+    fn sizes_are_clamped_on_load_and_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ui_state.ron");
+        let minimized = UiState {
+            width: 0.0,
+            height: 0.0,
+            ..UiState::default()
+        };
+
+        minimized.save(&path);
+        let saved: UiState = store::load(&path).unwrap();
+        assert_eq!(Size::new(saved.width, saved.height), MIN_WINDOW_SIZE);
+
+        store::save(&minimized, &path);
+        let loaded = UiState::load(&path).unwrap();
+        assert_eq!(Size::new(loaded.width, loaded.height), MIN_WINDOW_SIZE);
+    }
+}